@@ -22,7 +22,7 @@ BUILD_TYPE="${1:-debug}"
 
 if [ "$BUILD_TYPE" = "release" ]; then
     echo "Building release binary..."
-    cargo build --release
+    cargo build --release --features onnx-ort
     echo ""
     echo "✅ Release binary built: target/release/synapsemodel-tee-server"
 else
@@ -26,4 +26,4 @@ echo "Log Level: ${RUST_LOG:-info}"
 echo ""
 
 # Run the server
-cargo run
+cargo run --features mock-inference
@@ -0,0 +1,6 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Request handlers built on the enclave core
+
+pub mod synapsemodel;
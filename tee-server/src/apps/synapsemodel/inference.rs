@@ -2,141 +2,204 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::apps::synapsemodel::types::*;
-use crate::models::ModelLoader;
+use crate::models::{concrete_shape, ModelEntry, ModelHandle, Postprocessing, Tensor, TensorData};
 use crate::{EnclaveError, Result};
-use tracing::{info, debug};
+use tracing::{debug, info};
 
-/// Run inference on input data
-pub async fn run_inference(
-    model_loader: &ModelLoader,
-    model_id: &str,
+/// Run `model`, configured by its manifest `entry`, on input data
+pub fn run_inference(
+    entry: &ModelEntry,
+    model: &ModelHandle,
     input_data: &serde_json::Value,
 ) -> Result<serde_json::Value> {
-    info!("Running inference with model: {}", model_id);
-    
-    match model_id {
-        "mnist-classifier" => run_mnist_inference(model_loader, input_data).await,
-        "sentiment-analysis" => run_sentiment_inference(input_data).await,
-        _ => Err(EnclaveError::ModelNotFound(format!("Unknown model: {}", model_id))),
-    }
-}
+    info!("Running inference with model: {}", entry.id);
 
-/// MNIST digit classification
-async fn run_mnist_inference(
-    model_loader: &ModelLoader,
-    input_data: &serde_json::Value,
-) -> Result<serde_json::Value> {
-    // Parse input
-    let mnist_input: MNISTInput = serde_json::from_value(input_data.clone())
-        .map_err(|e| EnclaveError::ValidationError(format!("Invalid MNIST input: {}", e)))?;
-    
-    // Validate input dimensions
-    if mnist_input.pixels.len() != 784 {
-        return Err(EnclaveError::ValidationError(
-            format!("MNIST input must have 784 pixels, got {}", mnist_input.pixels.len())
-        ));
+    // Raw tensors work with any model and get every output back
+    if input_data.get("inputs").is_some() {
+        let tensor_input: TensorInput = serde_json::from_value(input_data.clone())
+            .map_err(|e| EnclaveError::ValidationError(format!("Invalid tensor input: {}", e)))?;
+        let outputs = model.run(&tensor_input.inputs)?;
+        return Ok(serde_json::to_value(TensorOutput { outputs }).unwrap());
     }
-    
-    debug!("MNIST input validated: {} pixels", mnist_input.pixels.len());
-    
-    // Load model
-    let model = model_loader.load_model("mnist-classifier")?;
-    
-    // Prepare input tensor (1, 1, 28, 28)
-    let input_tensor = prepare_mnist_tensor(&mnist_input.pixels);
-    
+
+    let declared = model
+        .inputs()
+        .first()
+        .map(|input| input.shape.clone())
+        .unwrap_or_default();
+    let input_tensor = input_tensor(entry, &declared, input_data)?;
+
     // Run inference
     let start_time = std::time::Instant::now();
-    let output = model.run_inference(&input_tensor)?;
+    let outputs = model.run(&[input_tensor])?;
     let inference_time = start_time.elapsed().as_millis() as u64;
-    
+
     debug!("Inference completed in {}ms", inference_time);
-    
-    // Parse output
-    let probabilities = output.to_vec();
+
+    postprocess(entry, outputs)
+}
+
+/// Build the model input from the request field named in the manifest
+///
+/// `declared` is the shape of the model's first input, used when the
+/// manifest gives none.
+fn input_tensor(
+    entry: &ModelEntry,
+    declared: &[i64],
+    input_data: &serde_json::Value,
+) -> Result<Tensor> {
+    let spec = entry.input.as_ref().ok_or_else(|| {
+        EnclaveError::ValidationError(format!(
+            "Model {} only takes raw tensors in \"inputs\"",
+            entry.id
+        ))
+    })?;
+    let value = std::iter::once(&spec.field)
+        .chain(&spec.aliases)
+        .find_map(|field| input_data.get(field))
+        .ok_or_else(|| {
+            EnclaveError::ValidationError(format!("Missing input field {:?}", spec.field))
+        })?;
+
+    // A single string, e.g. `{"text": "..."}`, is a one-element tensor
+    let values = match value {
+        serde_json::Value::String(_) => serde_json::Value::Array(vec![value.clone()]),
+        _ => value.clone(),
+    };
+    let data: TensorData = serde_json::from_value(
+        serde_json::json!({"dtype": spec.dtype, "data": values}),
+    )
+    .map_err(|e| {
+        EnclaveError::ValidationError(format!(
+            "Invalid {} input {:?}: {}",
+            spec.dtype, spec.field, e
+        ))
+    })?;
+    debug!("Input {:?}: {} values", spec.field, data.len());
+
+    let data = match data {
+        TensorData::F32(mut values) => {
+            entry.preprocessing.apply(&mut values);
+            TensorData::F32(values)
+        }
+        other => other,
+    };
+
+    // Shape the flat values like the model input, e.g. (1, 1, 28, 28)
+    let shape = if spec.shape.is_empty() {
+        declared
+    } else {
+        &spec.shape
+    };
+    let shape = concrete_shape(shape, data.len())?
+        .into_iter()
+        .map(|dim| dim as usize)
+        .collect();
+    Tensor::new("", shape, data)
+}
+
+/// Turn the graph outputs into the `result` the manifest asks for
+fn postprocess(entry: &ModelEntry, outputs: Vec<Tensor>) -> Result<serde_json::Value> {
+    let softmax = match entry.postprocessing {
+        Postprocessing::None => return Ok(serde_json::to_value(TensorOutput { outputs }).unwrap()),
+        Postprocessing::Classification { softmax } => softmax,
+    };
+
+    let mut probabilities = outputs
+        .first()
+        .and_then(Tensor::as_f32)
+        .filter(|probabilities| !probabilities.is_empty())
+        .ok_or_else(|| {
+            EnclaveError::InferenceError("Model returned no f32 probabilities".to_string())
+        })?
+        .to_vec();
+    if softmax {
+        let max = probabilities
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let total: f32 = probabilities.iter().map(|p| (p - max).exp()).sum();
+        for p in probabilities.iter_mut() {
+            *p = (*p - max).exp() / total;
+        }
+    }
+
     let prediction = probabilities
         .iter()
         .enumerate()
-        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
         .map(|(idx, _)| idx)
         .unwrap_or(0);
-    
-    let confidence = probabilities[prediction];
-    
+
     let result = ClassificationResult {
         prediction,
-        confidence,
+        confidence: probabilities[prediction],
         probabilities,
+        label: entry.labels.get(prediction).cloned(),
     };
-    
-    Ok(serde_json::to_value(result).unwrap())
-}
-
-/// Prepare MNIST tensor
-fn prepare_mnist_tensor(pixels: &[f32]) -> Vec<f32> {
-    // Normalize pixels to [0, 1] range if needed
-    pixels.iter()
-        .map(|&p| {
-            if p > 1.0 {
-                p / 255.0 // Normalize from [0, 255] to [0, 1]
-            } else {
-                p
-            }
-        })
-        .collect()
-}
 
-/// Sentiment analysis (placeholder implementation)
-async fn run_sentiment_inference(input_data: &serde_json::Value) -> Result<serde_json::Value> {
-    // Parse input
-    let sentiment_input: SentimentInput = serde_json::from_value(input_data.clone())
-        .map_err(|e| EnclaveError::ValidationError(format!("Invalid sentiment input: {}", e)))?;
-    
-    debug!("Sentiment input: {} characters", sentiment_input.text.len());
-    
-    // Simple rule-based sentiment (placeholder)
-    let text_lower = sentiment_input.text.to_lowercase();
-    let positive_words = ["good", "great", "excellent", "amazing", "wonderful"];
-    let negative_words = ["bad", "terrible", "awful", "horrible", "poor"];
-    
-    let positive_count = positive_words.iter()
-        .filter(|&word| text_lower.contains(word))
-        .count();
-    
-    let negative_count = negative_words.iter()
-        .filter(|&word| text_lower.contains(word))
-        .count();
-    
-    let (prediction, confidence) = if positive_count > negative_count {
-        (1, 0.75) // Positive
-    } else if negative_count > positive_count {
-        (0, 0.75) // Negative
-    } else {
-        (2, 0.5) // Neutral
-    };
-    
-    let result = ClassificationResult {
-        prediction,
-        confidence,
-        probabilities: vec![
-            if prediction == 0 { confidence } else { 1.0 - confidence },
-            if prediction == 1 { confidence } else { 1.0 - confidence },
-            if prediction == 2 { confidence } else { 1.0 - confidence },
-        ],
-    };
-    
     Ok(serde_json::to_value(result).unwrap())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::models::ModelManifest;
+    use serde_json::json;
+
+    const MANIFEST: &str = r#"
+        [[model]]
+        id = "mnist-classifier"
+        file = "mnist.onnx"
+        labels = ["0", "1", "2"]
+
+        [model.input]
+        field = "pixels"
+        aliases = ["data"]
+        dtype = "f32"
+
+        [model.preprocessing]
+        kind = "scale_to_unit"
+
+        [model.postprocessing]
+        kind = "classification"
+        softmax = true
+
+        [[model]]
+        id = "sentiment-analysis"
+        file = "sentiment.onnx"
+
+        [model.input]
+        field = "text"
+        dtype = "string"
+        shape = [1]
+    "#;
+
     #[test]
-    fn test_prepare_mnist_tensor() {
-        let pixels = vec![255.0; 784];
-        let tensor = prepare_mnist_tensor(&pixels);
-        assert_eq!(tensor.len(), 784);
-        assert!(tensor.iter().all(|&p| p >= 0.0 && p <= 1.0));
+    fn test_manifest_pipeline() {
+        let manifest = ModelManifest::from_toml_str(MANIFEST).unwrap();
+        let (mnist, sentiment) = (&manifest.models[0], &manifest.models[1]);
+
+        let tensor = input_tensor(mnist, &[-1, 4], &json!({"data": vec![255.0; 8]})).unwrap();
+        assert_eq!(tensor.shape(), [2, 4]);
+        assert!(tensor.as_f32().unwrap().iter().all(|&p| p == 1.0));
+        assert!(input_tensor(mnist, &[-1, 4], &json!({"pixels": vec![1.0; 3]})).is_err());
+        assert!(input_tensor(mnist, &[-1, 4], &json!({"text": "hi"})).is_err());
+
+        let tensor = input_tensor(sentiment, &[], &json!({"text": "great"})).unwrap();
+        assert_eq!(
+            tensor.data(),
+            &TensorData::String(vec!["great".to_string()])
+        );
+
+        let logits = Tensor::new("", vec![1, 3], TensorData::F32(vec![0.0, 2.0, 1.0])).unwrap();
+        let result: ClassificationResult =
+            serde_json::from_value(postprocess(mnist, vec![logits.clone()]).unwrap()).unwrap();
+        assert_eq!(result.prediction, 1);
+        assert_eq!(result.label.as_deref(), Some("1"));
+        assert!((result.probabilities.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        let result = postprocess(sentiment, vec![logits]).unwrap();
+        assert_eq!(result["outputs"][0]["data"], json!([0.0, 2.0, 1.0]));
     }
 }
@@ -1,45 +1,36 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
+use crate::models::Tensor;
 use serde::{Deserialize, Serialize};
 
+pub use crate::common::types::{ComputationMetadata, InferenceResponse};
+
 /// Inference request from backend
 #[derive(Debug, Clone, Deserialize)]
 pub struct InferenceRequest {
     pub job_id: String,
     pub model_id: String,
     pub input_data: serde_json::Value,
+    /// Hex-encoded SHA-256 the model file must have, e.g. from the on-chain listing
+    #[serde(default)]
+    pub model_sha256: Option<String>,
+    /// Hex-encoded Blake2b-256 the model file must have
+    #[serde(default)]
+    pub model_blake2b256: Option<String>,
 }
 
-/// Inference response to backend
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InferenceResponse {
-    pub job_id: String,
-    pub model_id: String,
-    pub result: serde_json::Value,
-    pub input_hash: String,
-    pub computation_metadata: ComputationMetadata,
+/// Raw tensors for any model, e.g.
+/// `{"inputs": [{"name": "input", "shape": [1, 4], "dtype": "f32", "data": [...]}]}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TensorInput {
+    pub inputs: Vec<Tensor>,
 }
 
-/// Computation metadata
+/// Every graph output of a raw tensor request
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComputationMetadata {
-    pub timestamp: u64,
-    pub model_version: String,
-    pub inference_time_ms: u64,
-}
-
-/// MNIST specific types
-#[derive(Debug, Deserialize)]
-pub struct MNISTInput {
-    #[serde(alias = "pixels", alias = "data")]
-    pub pixels: Vec<f32>,
-}
-
-/// Sentiment analysis input
-#[derive(Debug, Deserialize)]
-pub struct SentimentInput {
-    pub text: String,
+pub struct TensorOutput {
+    pub outputs: Vec<Tensor>,
 }
 
 /// Classification result
@@ -48,4 +39,7 @@ pub struct ClassificationResult {
     pub prediction: usize,
     pub confidence: f32,
     pub probabilities: Vec<f32>,
+    /// Manifest label of `prediction`, if the model lists labels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
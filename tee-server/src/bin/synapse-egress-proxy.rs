@@ -0,0 +1,73 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Egress proxy on the parent instance for a SynapseModel enclave
+//!
+//! Accepts HTTP proxy requests from the enclave over vsock and connects
+//! only to allowlisted destinations:
+//!
+//! ```bash
+//! synapse-egress-proxy --port 8001 \
+//!     --allow aggregator.walrus-testnet.walrus.space:443 \
+//!     --allow fullnode.testnet.sui.io:443
+//! ```
+
+use clap::Parser;
+use synapsemodel_tee_server::egress::Allowlist;
+use tracing::{info, warn};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "synapse-egress-proxy",
+    about = "Proxy outbound traffic from a SynapseModel enclave to allowlisted hosts"
+)]
+struct Args {
+    /// Allowed destination: host, host:port, *.domain or *.domain:port; repeatable
+    #[arg(long = "allow", value_name = "HOST", required = true)]
+    allow: Vec<String>,
+
+    /// vsock port to accept the enclave on, its EGRESS_VSOCK_PORT
+    #[arg(long, default_value_t = 8001)]
+    port: u32,
+
+    /// CID to listen on; default any
+    #[arg(long, default_value_t = u32::MAX)]
+    cid: u32,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+        )
+        .init();
+
+    let allowlist = Allowlist::new(&args.allow).map_err(anyhow::Error::msg)?;
+    if allowlist.allows("169.254.169.254", 80) {
+        warn!("The allowlist lets the enclave reach the instance metadata service");
+    }
+    info!("Allowed destinations: {}", allowlist);
+    serve(args, allowlist).await
+}
+
+#[cfg(target_os = "linux")]
+async fn serve(args: Args, allowlist: Allowlist) -> anyhow::Result<()> {
+    use synapsemodel_tee_server::{egress, vsock::VsockListener};
+
+    let listener = VsockListener::bind(args.cid, args.port)?;
+    let addr = listener.local_addr()?;
+    info!(
+        "Egress proxy listening on vsock cid {} port {}",
+        addr.cid(),
+        addr.port()
+    );
+    egress::serve(listener, std::sync::Arc::new(allowlist)).await?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn serve(_args: Args, _allowlist: Allowlist) -> anyhow::Result<()> {
+    anyhow::bail!("vsock is only supported on Linux")
+}
@@ -0,0 +1,91 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verify a stored `/process_data` response without running the server
+//!
+//! ```bash
+//! synapse-verify --response response.json --public-key <hex> --input input.json
+//! synapse-verify --response response.json --keys get_pk.json
+//! ```
+
+use clap::Parser;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use synapsemodel_tee_server::common::{PublicKeyResponse, SignedInferenceResponse};
+use synapsemodel_tee_server::verify::{verify_response, verify_response_with_keys, CheckStatus};
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "synapse-verify",
+    about = "Verify signed SynapseModel inference responses"
+)]
+struct Args {
+    /// Path to the response JSON returned by `/process_data`
+    #[arg(long)]
+    response: PathBuf,
+
+    /// Enclave public key (hex, as served by `/get_pk`)
+    #[arg(long, required_unless_present = "keys", conflicts_with = "keys")]
+    public_key: Option<String>,
+
+    /// Path to a saved `/get_pk` response; selects the key by the response's key id
+    #[arg(long)]
+    keys: Option<PathBuf>,
+
+    /// Path to the original `input_data` JSON, used to recompute `input_hash`
+    #[arg(long)]
+    input: Option<PathBuf>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &PathBuf) -> anyhow::Result<T> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    let args = Args::parse();
+
+    let response: SignedInferenceResponse = read_json(&args.response)?;
+    let input: Option<serde_json::Value> = args.input.as_ref().map(read_json).transpose()?;
+
+    let report = match (&args.public_key, &args.keys) {
+        (Some(public_key), _) => verify_response(&response, public_key, input.as_ref()),
+        (None, Some(keys_path)) => {
+            let keys: PublicKeyResponse = read_json(keys_path)?;
+            verify_response_with_keys(&response, &keys.keys, input.as_ref())
+        }
+        (None, None) => unreachable!("clap requires --public-key or --keys"),
+    };
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for check in &report.checks {
+            match &check.status {
+                CheckStatus::Passed => println!("[PASS] {}", check.name),
+                CheckStatus::Failed(reason) => println!("[FAIL] {}: {}", check.name, reason),
+                CheckStatus::Skipped(reason) => println!("[SKIP] {}: {}", check.name, reason),
+            }
+        }
+        println!(
+            "Result: {}",
+            if report.is_valid() {
+                "VALID"
+            } else {
+                "INVALID"
+            }
+        );
+    }
+
+    Ok(if report.is_valid() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
@@ -0,0 +1,61 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Relay TCP clients on the parent instance to the enclave's vsock port
+//!
+//! ```bash
+//! synapse-vsock-forward --cid 16 --port 3000 --listen 0.0.0.0:3000
+//! ```
+
+use clap::Parser;
+use std::net::SocketAddr;
+use tracing::info;
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "synapse-vsock-forward",
+    about = "Forward TCP connections to a SynapseModel enclave over vsock"
+)]
+struct Args {
+    /// TCP address to accept clients on
+    #[arg(long, default_value = "0.0.0.0:3000")]
+    listen: SocketAddr,
+
+    /// Enclave CID, as printed by `nitro-cli describe-enclaves`
+    #[arg(long)]
+    cid: u32,
+
+    /// vsock port the enclave server listens on
+    #[arg(long, default_value_t = 3000)]
+    port: u32,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "info".into()),
+        )
+        .init();
+
+    let listener = tokio::net::TcpListener::bind(args.listen).await?;
+    info!(
+        "Forwarding {} to vsock cid {} port {}",
+        listener.local_addr()?,
+        args.cid,
+        args.port
+    );
+    forward(listener, args).await
+}
+
+#[cfg(target_os = "linux")]
+async fn forward(listener: tokio::net::TcpListener, args: Args) -> anyhow::Result<()> {
+    synapsemodel_tee_server::vsock::forward_tcp(listener, args.cid, args.port).await?;
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn forward(_listener: tokio::net::TcpListener, _args: Args) -> anyhow::Result<()> {
+    anyhow::bail!("vsock is only supported on Linux")
+}
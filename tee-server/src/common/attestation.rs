@@ -1,44 +0,0 @@
-// Copyright (c) 2025, SynapseModel Team
-// SPDX-License-Identifier: Apache-2.0
-
-use tracing::info;
-
-/// Get attestation document from AWS Nitro Enclave
-/// 
-/// In production, this would communicate with the Nitro hypervisor
-/// to retrieve a cryptographically signed attestation document
-pub fn get_attestation_document() -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-    #[cfg(target_os = "linux")]
-    {
-        // In production, use nsm (Nitro Secure Module) to get real attestation
-        // For now, return a placeholder
-        info!("Attempting to get attestation from Nitro Secure Module");
-        
-        // TODO: Implement actual NSM communication
-        // let nsm = nsm_driver::nsm_init();
-        // let attestation = nsm.get_attestation_document(...);
-        
-        Err("Attestation not available in development mode".into())
-    }
-    
-    #[cfg(not(target_os = "linux"))]
-    {
-        Err("Attestation only available on Linux (AWS Nitro)".into())
-    }
-}
-
-/// Verify PCR values (Platform Configuration Registers)
-/// 
-/// PCRs are cryptographic measurements of the enclave boot process
-pub fn verify_pcrs(_expected_pcrs: &[Vec<u8>; 3]) -> bool {
-    // In production, this would verify the current PCRs match expected values
-    // For development, always return true
-    
-    if cfg!(debug_assertions) {
-        tracing::warn!("PCR verification skipped in development mode");
-        return true;
-    }
-    
-    // TODO: Implement actual PCR verification
-    true
-}
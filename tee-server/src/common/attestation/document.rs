@@ -0,0 +1,249 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! AWS Nitro attestation document and its COSE_Sign1 envelope
+
+use crate::{EnclaveError, Result};
+use ciborium::value::Value;
+use std::collections::BTreeMap;
+
+/// COSE algorithm identifier for ECDSA with SHA-384
+pub const COSE_ALG_ES384: i64 = -35;
+
+/// Payload of a Nitro attestation document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttestationDocument {
+    pub module_id: String,
+    /// Digest used for PCRs, always `SHA384` on Nitro
+    pub digest: String,
+    /// Milliseconds since the UNIX epoch
+    pub timestamp: u64,
+    pub pcrs: BTreeMap<u16, Vec<u8>>,
+    /// DER-encoded certificate whose key signed the document
+    pub certificate: Vec<u8>,
+    /// DER-encoded CA chain, root first
+    pub cabundle: Vec<Vec<u8>>,
+    pub public_key: Option<Vec<u8>>,
+    pub user_data: Option<Vec<u8>>,
+    pub nonce: Option<Vec<u8>>,
+}
+
+impl AttestationDocument {
+    /// CBOR map in the field order used by the Nitro hypervisor
+    pub fn to_cbor_value(&self) -> Value {
+        let optional_bytes = |field: &Option<Vec<u8>>| match field {
+            Some(bytes) => Value::Bytes(bytes.clone()),
+            None => Value::Null,
+        };
+
+        Value::Map(vec![
+            (text("module_id"), Value::Text(self.module_id.clone())),
+            (text("digest"), Value::Text(self.digest.clone())),
+            (text("timestamp"), Value::Integer(self.timestamp.into())),
+            (
+                text("pcrs"),
+                Value::Map(
+                    self.pcrs
+                        .iter()
+                        .map(|(index, value)| {
+                            (Value::Integer((*index).into()), Value::Bytes(value.clone()))
+                        })
+                        .collect(),
+                ),
+            ),
+            (text("certificate"), Value::Bytes(self.certificate.clone())),
+            (
+                text("cabundle"),
+                Value::Array(self.cabundle.iter().cloned().map(Value::Bytes).collect()),
+            ),
+            (text("public_key"), optional_bytes(&self.public_key)),
+            (text("user_data"), optional_bytes(&self.user_data)),
+            (text("nonce"), optional_bytes(&self.nonce)),
+        ])
+    }
+
+    pub fn to_cbor(&self) -> Vec<u8> {
+        encode(&self.to_cbor_value())
+    }
+
+    /// Decode the payload of a COSE_Sign1 document without verifying it
+    pub fn from_cose_sign1(bytes: &[u8]) -> Result<Self> {
+        Self::from_cbor(&CoseSign1::from_cbor(bytes)?.payload)
+    }
+
+    /// Decode the COSE_Sign1 payload into a typed document
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let value = decode(bytes)?;
+        let fields = value
+            .as_map()
+            .ok_or_else(|| malformed("document is not a map"))?;
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(k, _)| k.as_text() == Some(name))
+                .map(|(_, v)| v)
+        };
+        let required =
+            |name: &str| field(name).ok_or_else(|| malformed(&format!("missing {}", name)));
+        let bytes_field = |name: &str| -> Result<Vec<u8>> {
+            required(name)?
+                .as_bytes()
+                .cloned()
+                .ok_or_else(|| malformed(&format!("{} is not a byte string", name)))
+        };
+        let optional_bytes = |name: &str| -> Result<Option<Vec<u8>>> {
+            match field(name) {
+                None | Some(Value::Null) => Ok(None),
+                Some(Value::Bytes(bytes)) => Ok(Some(bytes.clone())),
+                Some(_) => Err(malformed(&format!("{} is not a byte string", name))),
+            }
+        };
+        let text_field = |name: &str| -> Result<String> {
+            required(name)?
+                .as_text()
+                .map(str::to_string)
+                .ok_or_else(|| malformed(&format!("{} is not text", name)))
+        };
+
+        let timestamp = required("timestamp")?
+            .as_integer()
+            .and_then(|i| u64::try_from(i).ok())
+            .ok_or_else(|| malformed("timestamp is not an unsigned integer"))?;
+
+        let pcrs = required("pcrs")?
+            .as_map()
+            .ok_or_else(|| malformed("pcrs is not a map"))?
+            .iter()
+            .map(|(index, value)| {
+                let index = index
+                    .as_integer()
+                    .and_then(|i| u16::try_from(i).ok())
+                    .ok_or_else(|| malformed("PCR index is not an integer"))?;
+                let value = value
+                    .as_bytes()
+                    .cloned()
+                    .ok_or_else(|| malformed("PCR value is not a byte string"))?;
+                Ok((index, value))
+            })
+            .collect::<Result<BTreeMap<_, _>>>()?;
+
+        let cabundle = required("cabundle")?
+            .as_array()
+            .ok_or_else(|| malformed("cabundle is not an array"))?
+            .iter()
+            .map(|cert| {
+                cert.as_bytes()
+                    .cloned()
+                    .ok_or_else(|| malformed("cabundle entry is not a byte string"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            module_id: text_field("module_id")?,
+            digest: text_field("digest")?,
+            timestamp,
+            pcrs,
+            certificate: bytes_field("certificate")?,
+            cabundle,
+            public_key: optional_bytes("public_key")?,
+            user_data: optional_bytes("user_data")?,
+            nonce: optional_bytes("nonce")?,
+        })
+    }
+}
+
+/// Untagged COSE_Sign1 structure (RFC 8152 §4.2) as emitted by the NSM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoseSign1 {
+    /// Serialized protected header map
+    pub protected: Vec<u8>,
+    pub payload: Vec<u8>,
+    /// Raw `r || s` ECDSA signature
+    pub signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    /// Protected header declaring ES384, as used by Nitro
+    pub fn es384_protected_header() -> Vec<u8> {
+        encode(&Value::Map(vec![(
+            Value::Integer(1.into()),
+            Value::Integer(COSE_ALG_ES384.into()),
+        )]))
+    }
+
+    /// `Sig_structure` bytes that the signature covers
+    pub fn sig_structure(protected: &[u8], payload: &[u8]) -> Vec<u8> {
+        encode(&Value::Array(vec![
+            Value::Text("Signature1".to_string()),
+            Value::Bytes(protected.to_vec()),
+            Value::Bytes(Vec::new()),
+            Value::Bytes(payload.to_vec()),
+        ]))
+    }
+
+    /// Decode an untagged or tag-18 COSE_Sign1 array
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self> {
+        let value = match decode(bytes)? {
+            Value::Tag(18, inner) => *inner,
+            value => value,
+        };
+        let parts = value
+            .as_array()
+            .filter(|parts| parts.len() == 4)
+            .ok_or_else(|| malformed("COSE_Sign1 must be a 4-element array"))?;
+        let bytes_at = |index: usize, name: &str| {
+            parts[index]
+                .as_bytes()
+                .cloned()
+                .ok_or_else(|| malformed(&format!("COSE_Sign1 {} is not a byte string", name)))
+        };
+
+        Ok(Self {
+            protected: bytes_at(0, "protected header")?,
+            payload: bytes_at(2, "payload")?,
+            signature: bytes_at(3, "signature")?,
+        })
+    }
+
+    /// Algorithm declared in the protected header
+    pub fn algorithm(&self) -> Result<i64> {
+        decode(&self.protected)?
+            .as_map()
+            .and_then(|header| {
+                header
+                    .iter()
+                    .find(|(k, _)| k.as_integer() == Some(1.into()))
+                    .and_then(|(_, v)| v.as_integer())
+            })
+            .and_then(|alg| i64::try_from(alg).ok())
+            .ok_or_else(|| malformed("protected header has no algorithm"))
+    }
+
+    pub fn to_cbor(&self) -> Vec<u8> {
+        encode(&Value::Array(vec![
+            Value::Bytes(self.protected.clone()),
+            Value::Map(Vec::new()),
+            Value::Bytes(self.payload.clone()),
+            Value::Bytes(self.signature.clone()),
+        ]))
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn decode(bytes: &[u8]) -> Result<Value> {
+    ciborium::de::from_reader(bytes)
+        .map_err(|e| EnclaveError::AttestationError(format!("Invalid CBOR: {}", e)))
+}
+
+fn malformed(reason: &str) -> EnclaveError {
+    EnclaveError::AttestationError(format!("Malformed attestation document: {}", reason))
+}
+
+pub(crate) fn encode(value: &Value) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes).expect("CBOR encoding into a Vec cannot fail");
+    bytes
+}
@@ -0,0 +1,170 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Software NSM for development and tests
+//!
+//! Emits COSE_Sign1 documents with the same structure as the Nitro
+//! hypervisor, signed by a freshly generated P-384 test CA. Serves as the
+//! `software` attestation provider.
+
+use crate::common::attestation::document::{AttestationDocument, CoseSign1};
+use crate::common::attestation::{AttestationProvider, AttestationRequest, ProviderKind};
+use crate::{EnclaveError, Result};
+use p384::ecdsa::signature::Signer;
+use p384::ecdsa::{Signature, SigningKey};
+use p384::pkcs8::DecodePrivateKey;
+use rcgen::{
+    BasicConstraints, CertificateParams, DistinguishedName, DnType, IsCa, KeyPair,
+    PKCS_ECDSA_P384_SHA384,
+};
+use std::collections::BTreeMap;
+
+/// Number of PCRs reported by the mock (matches Nitro)
+const PCR_COUNT: u16 = 16;
+
+/// Mock Nitro Secure Module backed by a test CA
+pub struct MockNsm {
+    module_id: String,
+    root_certificate: Vec<u8>,
+    certificate: Vec<u8>,
+    signing_key: SigningKey,
+    pcrs: BTreeMap<u16, Vec<u8>>,
+}
+
+impl MockNsm {
+    /// Create a mock with a new test CA and all-zero PCRs
+    pub fn new() -> Result<Self> {
+        let mock_err =
+            |e: rcgen::Error| EnclaveError::AttestationError(format!("Mock CA error: {}", e));
+
+        let root_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384).map_err(mock_err)?;
+        let mut root_params = CertificateParams::new(Vec::<String>::new()).map_err(mock_err)?;
+        root_params.distinguished_name = distinguished_name("synapsemodel-test-root");
+        root_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let root = root_params.self_signed(&root_key).map_err(mock_err)?;
+
+        let leaf_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384).map_err(mock_err)?;
+        let mut leaf_params = CertificateParams::new(Vec::<String>::new()).map_err(mock_err)?;
+        leaf_params.distinguished_name = distinguished_name("synapsemodel-mock-enclave");
+        let leaf = leaf_params
+            .signed_by(&leaf_key, &root, &root_key)
+            .map_err(mock_err)?;
+
+        let signing_key = SigningKey::from_pkcs8_der(&leaf_key.serialize_der())
+            .map_err(|e| EnclaveError::AttestationError(format!("Mock key error: {}", e)))?;
+
+        Ok(Self {
+            module_id: "i-mock-enc-0000000000000000".to_string(),
+            root_certificate: root.der().to_vec(),
+            certificate: leaf.der().to_vec(),
+            signing_key,
+            pcrs: (0..PCR_COUNT).map(|index| (index, vec![0u8; 48])).collect(),
+        })
+    }
+
+    /// Override a PCR value
+    pub fn with_pcr(mut self, index: u16, value: Vec<u8>) -> Self {
+        self.pcrs.insert(index, value);
+        self
+    }
+
+    /// DER-encoded test root, to be trusted instead of the AWS Nitro root
+    pub fn root_certificate_der(&self) -> &[u8] {
+        &self.root_certificate
+    }
+
+    pub fn pcrs(&self) -> &BTreeMap<u16, Vec<u8>> {
+        &self.pcrs
+    }
+}
+
+impl AttestationProvider for MockNsm {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Software
+    }
+
+    fn attest(&self, request: &AttestationRequest) -> Result<Vec<u8>> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| EnclaveError::GenericError(format!("Failed to get timestamp: {}", e)))?
+            .as_millis() as u64;
+
+        let document = AttestationDocument {
+            module_id: self.module_id.clone(),
+            digest: "SHA384".to_string(),
+            timestamp,
+            pcrs: self.pcrs.clone(),
+            certificate: self.certificate.clone(),
+            cabundle: vec![self.root_certificate.clone()],
+            public_key: request.public_key.clone(),
+            user_data: request.user_data.clone(),
+            nonce: request.nonce.clone(),
+        };
+
+        let protected = CoseSign1::es384_protected_header();
+        let payload = document.to_cbor();
+        let signature: Signature = self
+            .signing_key
+            .sign(&CoseSign1::sig_structure(&protected, &payload));
+
+        Ok(CoseSign1 {
+            protected,
+            payload,
+            signature: signature.to_bytes().to_vec(),
+        }
+        .to_cbor())
+    }
+}
+
+fn distinguished_name(common_name: &str) -> DistinguishedName {
+    let mut name = DistinguishedName::new();
+    name.push(DnType::CommonName, common_name);
+    name.push(DnType::OrganizationName, "SynapseModel Test CA");
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ciborium::value::Value;
+    use p384::ecdsa::signature::Verifier;
+
+    #[test]
+    fn test_mock_document_is_signed_cose_sign1() {
+        let nsm = MockNsm::new().unwrap().with_pcr(0, vec![0xAA; 48]);
+        let request = AttestationRequest {
+            user_data: Some(b"signing-key".to_vec()),
+            nonce: Some(b"nonce".to_vec()),
+            public_key: None,
+        };
+        let bytes = nsm.attest(&request).unwrap();
+
+        let cose: Value = ciborium::de::from_reader(bytes.as_slice()).unwrap();
+        let parts = cose.as_array().unwrap();
+        assert_eq!(parts.len(), 4);
+        let protected = parts[0].as_bytes().unwrap();
+        let payload = parts[2].as_bytes().unwrap();
+        let signature = Signature::from_slice(parts[3].as_bytes().unwrap()).unwrap();
+
+        nsm.signing_key
+            .verifying_key()
+            .verify(&CoseSign1::sig_structure(protected, payload), &signature)
+            .unwrap();
+
+        let document: Value = ciborium::de::from_reader(payload.as_slice()).unwrap();
+        let field = |name: &str| {
+            document
+                .as_map()
+                .unwrap()
+                .iter()
+                .find(|(k, _)| k.as_text() == Some(name))
+                .map(|(_, v)| v.clone())
+                .unwrap()
+        };
+        assert_eq!(field("user_data"), Value::Bytes(b"signing-key".to_vec()));
+        assert_eq!(field("nonce"), Value::Bytes(b"nonce".to_vec()));
+        assert_eq!(field("public_key"), Value::Null);
+        assert_eq!(field("digest"), Value::Text("SHA384".to_string()));
+        assert_eq!(field("pcrs").as_map().unwrap().len(), PCR_COUNT as usize);
+    }
+}
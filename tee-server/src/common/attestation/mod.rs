@@ -0,0 +1,109 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+pub mod document;
+pub mod mock;
+pub mod nsm;
+pub mod policy;
+pub mod provider;
+pub mod tsm;
+pub mod verifier;
+
+pub use document::*;
+pub use mock::MockNsm;
+pub use nsm::{NitroNsm, NSM_DEVICE_PATH};
+pub use policy::*;
+pub use provider::*;
+pub use tsm::{report_data, TsmProvider, TSM_REPORT_PATH};
+pub use verifier::*;
+
+use crate::{EnclaveError, Result};
+
+/// Largest nonce the NSM accepts
+pub const MAX_NONCE_LENGTH: usize = 512;
+
+/// Parameters of an NSM attestation request
+#[derive(Debug, Clone, Default)]
+pub struct AttestationRequest {
+    pub user_data: Option<Vec<u8>>,
+    pub nonce: Option<Vec<u8>>,
+    pub public_key: Option<Vec<u8>>,
+}
+
+/// Build `user_data` as `public_key || models_root`
+///
+/// The signing key binds responses to the enclave; the Merkle root of the
+/// model files in the cache (see `models::measurement`) binds the weights.
+pub fn attestation_user_data(public_key: &[u8], models_root: &[u8; 32]) -> Vec<u8> {
+    [public_key, models_root.as_slice()].concat()
+}
+
+/// Get attestation evidence from the configured provider
+///
+/// `user_data` carries the enclave's signing public key so verifiers can
+/// bind signed responses to the attested enclave.
+pub fn get_attestation_document(
+    provider: &dyn AttestationProvider,
+    user_data: &[u8],
+    nonce: Option<&[u8]>,
+    public_key: Option<&[u8]>,
+) -> Result<Vec<u8>> {
+    provider.attest(&AttestationRequest {
+        user_data: Some(user_data.to_vec()),
+        nonce: nonce.map(<[u8]>::to_vec),
+        public_key: public_key.map(<[u8]>::to_vec),
+    })
+}
+
+/// Decode a hex client nonce, enforcing the NSM size limit
+pub fn parse_nonce(nonce_hex: &str) -> Result<Vec<u8>> {
+    let nonce = hex::decode(nonce_hex)
+        .map_err(|e| EnclaveError::ValidationError(format!("Invalid nonce hex: {}", e)))?;
+    if nonce.is_empty() || nonce.len() > MAX_NONCE_LENGTH {
+        return Err(EnclaveError::ValidationError(format!(
+            "Nonce must be 1 to {} bytes",
+            MAX_NONCE_LENGTH
+        )));
+    }
+    Ok(nonce)
+}
+
+/// Verify PCR values (Platform Configuration Registers)
+///
+/// PCRs are cryptographic measurements of the enclave boot process. The
+/// provider is asked for a fresh document and its PCRs are checked against
+/// every release allowed by `policy`. Only Nitro-format providers have PCRs.
+pub fn verify_pcrs(provider: &dyn AttestationProvider, policy: &PcrPolicy) -> Result<PcrVerdict> {
+    if !provider.kind().has_pcrs() {
+        return Err(EnclaveError::AttestationError(format!(
+            "{} attestation does not report PCRs",
+            provider.kind()
+        )));
+    }
+    let document = provider.attest(&AttestationRequest::default())?;
+    let document = AttestationDocument::from_cose_sign1(&document)?;
+    Ok(policy.verify(&document.pcrs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_pcrs_with_mock_nsm() {
+        let nsm = MockNsm::new().unwrap().with_pcr(2, vec![0x22; PCR_LENGTH]);
+        let policy = |pcr2: u8| PcrPolicy {
+            releases: vec![ReleasePcrs {
+                name: "mock".to_string(),
+                pcr0: hex::encode([0u8; PCR_LENGTH]),
+                pcr1: hex::encode([0u8; PCR_LENGTH]),
+                pcr2: hex::encode([pcr2; PCR_LENGTH]),
+                pcr8: None,
+            }],
+        };
+
+        assert!(verify_pcrs(&nsm, &policy(0x22)).unwrap().is_allowed());
+        let verdict = verify_pcrs(&nsm, &policy(0x00)).unwrap();
+        assert_eq!(verdict.releases[0].mismatches[0].index, 2);
+    }
+}
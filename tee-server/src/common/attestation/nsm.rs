@@ -0,0 +1,224 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Nitro Secure Module driver
+//!
+//! Requests are CBOR-encoded `Request` enums exchanged with `/dev/nsm`
+//! through a single read/write ioctl, mirroring the AWS NSM API.
+
+use crate::common::attestation::document::encode;
+use crate::common::attestation::{AttestationProvider, AttestationRequest, ProviderKind};
+use crate::{EnclaveError, Result};
+use ciborium::value::Value;
+use std::path::PathBuf;
+use tracing::debug;
+
+/// Default NSM device node inside a Nitro Enclave
+pub const NSM_DEVICE_PATH: &str = "/dev/nsm";
+
+/// Largest response the NSM may return
+const NSM_RESPONSE_MAX_SIZE: usize = 0x3000;
+
+/// Attestation via the Nitro Secure Module device
+pub struct NitroNsm {
+    path: PathBuf,
+}
+
+impl NitroNsm {
+    pub fn new() -> Self {
+        Self::with_path(NSM_DEVICE_PATH)
+    }
+
+    pub fn with_path(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Whether the device node exists, i.e. we run inside an enclave
+    pub fn is_available(&self) -> bool {
+        self.path.exists()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn process_request(&self, request: &[u8]) -> Result<Vec<u8>> {
+        use nix::libc;
+        use std::os::fd::AsRawFd;
+
+        #[repr(C)]
+        struct NsmMessage {
+            request: libc::iovec,
+            response: libc::iovec,
+        }
+
+        nix::ioctl_readwrite!(nsm_ioctl, 0x0A, 0, NsmMessage);
+
+        let device = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.path)
+            .map_err(|e| {
+                EnclaveError::AttestationError(format!("Failed to open {:?}: {}", self.path, e))
+            })?;
+
+        let mut response = vec![0u8; NSM_RESPONSE_MAX_SIZE];
+        let mut message = NsmMessage {
+            request: libc::iovec {
+                iov_base: request.as_ptr() as *mut libc::c_void,
+                iov_len: request.len(),
+            },
+            response: libc::iovec {
+                iov_base: response.as_mut_ptr() as *mut libc::c_void,
+                iov_len: response.len(),
+            },
+        };
+
+        // SAFETY: both iovecs point at live buffers of the stated lengths for
+        // the duration of the call, and the kernel writes at most
+        // `response.len()` bytes before updating `iov_len`.
+        unsafe { nsm_ioctl(device.as_raw_fd(), &mut message) }
+            .map_err(|e| EnclaveError::AttestationError(format!("NSM ioctl failed: {}", e)))?;
+
+        response.truncate(message.response.iov_len);
+        Ok(response)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn process_request(&self, _request: &[u8]) -> Result<Vec<u8>> {
+        Err(EnclaveError::AttestationError(
+            "Attestation only available on Linux (AWS Nitro)".to_string(),
+        ))
+    }
+}
+
+impl Default for NitroNsm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttestationProvider for NitroNsm {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Nitro
+    }
+
+    fn attest(&self, request: &AttestationRequest) -> Result<Vec<u8>> {
+        debug!("Requesting attestation document from {:?}", self.path);
+        let response = self.process_request(&encode_attestation_request(request))?;
+        decode_attestation_response(&response)
+    }
+}
+
+/// Encode `Request::Attestation { user_data, nonce, public_key }`
+pub(crate) fn encode_attestation_request(request: &AttestationRequest) -> Vec<u8> {
+    let optional_bytes = |field: &Option<Vec<u8>>| match field {
+        Some(bytes) => Value::Bytes(bytes.clone()),
+        None => Value::Null,
+    };
+
+    encode(&Value::Map(vec![(
+        Value::Text("Attestation".to_string()),
+        Value::Map(vec![
+            (
+                Value::Text("user_data".to_string()),
+                optional_bytes(&request.user_data),
+            ),
+            (
+                Value::Text("nonce".to_string()),
+                optional_bytes(&request.nonce),
+            ),
+            (
+                Value::Text("public_key".to_string()),
+                optional_bytes(&request.public_key),
+            ),
+        ]),
+    )]))
+}
+
+/// Decode `Response::Attestation { document }` or `Response::Error(code)`
+pub(crate) fn decode_attestation_response(bytes: &[u8]) -> Result<Vec<u8>> {
+    let value: Value = ciborium::de::from_reader(bytes)
+        .map_err(|e| EnclaveError::AttestationError(format!("Invalid NSM response: {}", e)))?;
+
+    let invalid = || EnclaveError::AttestationError("Unexpected NSM response".to_string());
+    let Value::Map(entries) = value else {
+        return Err(invalid());
+    };
+    let (Some(Value::Text(variant)), Some(body)) =
+        (entries.first().map(|e| &e.0), entries.first().map(|e| &e.1))
+    else {
+        return Err(invalid());
+    };
+
+    match (variant.as_str(), body) {
+        ("Attestation", Value::Map(fields)) => {
+            let document = fields
+                .iter()
+                .find(|(k, _)| k.as_text() == Some("document"))
+                .map(|(_, v)| v)
+                .ok_or_else(invalid)?;
+            match document {
+                Value::Bytes(bytes) => Ok(bytes.clone()),
+                // The NSM API serializes the document as a plain byte array
+                Value::Array(items) => items
+                    .iter()
+                    .map(|item| {
+                        item.as_integer()
+                            .and_then(|i| u8::try_from(i).ok())
+                            .ok_or_else(invalid)
+                    })
+                    .collect(),
+                _ => Err(invalid()),
+            }
+        }
+        ("Error", code) => Err(EnclaveError::AttestationError(format!(
+            "NSM returned error: {}",
+            code.as_text().unwrap_or("unknown")
+        ))),
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_encoding() {
+        let request = AttestationRequest {
+            user_data: Some(vec![1, 2]),
+            nonce: None,
+            public_key: None,
+        };
+        let value: Value =
+            ciborium::de::from_reader(encode_attestation_request(&request).as_slice()).unwrap();
+        let body = &value.as_map().unwrap()[0];
+        assert_eq!(body.0.as_text(), Some("Attestation"));
+        let fields = body.1.as_map().unwrap();
+        assert_eq!(fields[0].1, Value::Bytes(vec![1, 2]));
+        assert_eq!(fields[1].1, Value::Null);
+    }
+
+    #[test]
+    fn test_response_decoding() {
+        let array_doc = encode(&Value::Map(vec![(
+            Value::Text("Attestation".to_string()),
+            Value::Map(vec![(
+                Value::Text("document".to_string()),
+                Value::Array(vec![
+                    Value::Integer(0xd2.into()),
+                    Value::Integer(0x84.into()),
+                ]),
+            )]),
+        )]));
+        assert_eq!(
+            decode_attestation_response(&array_doc).unwrap(),
+            vec![0xd2, 0x84]
+        );
+
+        let error = encode(&Value::Map(vec![(
+            Value::Text("Error".to_string()),
+            Value::Text("InvalidArgument".to_string()),
+        )]));
+        let err = decode_attestation_response(&error).unwrap_err();
+        assert!(err.to_string().contains("InvalidArgument"));
+    }
+}
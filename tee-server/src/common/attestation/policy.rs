@@ -0,0 +1,318 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! PCR policy: which enclave builds are allowed to run
+//!
+//! A policy lists one entry per release with its expected PCR0/1/2 (and
+//! optionally PCR8 for signed images). Several releases may be allowed at
+//! once so a rollout can run old and new builds side by side.
+
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// Size of a SHA-384 PCR value
+pub const PCR_LENGTH: usize = 48;
+
+/// Expected measurements of one enclave build
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleasePcrs {
+    /// Release label, e.g. `v1.2.0`
+    pub name: String,
+    /// Enclave image file
+    pub pcr0: String,
+    /// Linux kernel and bootstrap
+    pub pcr1: String,
+    /// Application
+    pub pcr2: String,
+    /// Signing certificate, only for signed images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pcr8: Option<String>,
+}
+
+impl ReleasePcrs {
+    /// `(index, hex value)` of each PCR this release pins
+    pub fn expected(&self) -> Vec<(u16, &str)> {
+        let mut expected = vec![
+            (0, self.pcr0.as_str()),
+            (1, self.pcr1.as_str()),
+            (2, self.pcr2.as_str()),
+        ];
+        if let Some(pcr8) = &self.pcr8 {
+            expected.push((8, pcr8.as_str()));
+        }
+        expected
+    }
+
+    /// All-zero PCR0/1/2, as reported by debug-mode enclaves and the mock NSM
+    pub fn is_debug(&self) -> bool {
+        [&self.pcr0, &self.pcr1, &self.pcr2]
+            .iter()
+            .all(|value| value.bytes().all(|b| b == b'0'))
+    }
+}
+
+/// Allowed enclave builds, loaded from TOML or JSON
+///
+/// ```toml
+/// [[release]]
+/// name = "v1.2.0"
+/// pcr0 = "..."
+/// pcr1 = "..."
+/// pcr2 = "..."
+/// pcr8 = "..."  # optional
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PcrPolicy {
+    #[serde(rename = "release")]
+    pub releases: Vec<ReleasePcrs>,
+}
+
+impl PcrPolicy {
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let policy: Self =
+            toml::from_str(s).map_err(|e| invalid_policy(&format!("invalid TOML: {}", e)))?;
+        policy.validate()
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        let policy: Self =
+            serde_json::from_str(s).map_err(|e| invalid_policy(&format!("invalid JSON: {}", e)))?;
+        policy.validate()
+    }
+
+    /// Load a policy file; `.json` files are parsed as JSON, anything else as TOML
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| invalid_policy(&format!("failed to read {:?}: {}", path, e)))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&contents),
+            _ => Self::from_toml_str(&contents),
+        }
+    }
+
+    /// Check reported PCRs against every allowed release
+    pub fn verify(&self, pcrs: &BTreeMap<u16, Vec<u8>>) -> PcrVerdict {
+        let releases: Vec<ReleaseVerdict> = self
+            .releases
+            .iter()
+            .map(|release| ReleaseVerdict {
+                release: release.name.clone(),
+                mismatches: release
+                    .expected()
+                    .into_iter()
+                    .filter_map(|(index, expected)| {
+                        let actual = pcrs.get(&index).map(hex::encode);
+                        match actual.as_deref() {
+                            Some(actual) if actual.eq_ignore_ascii_case(expected) => None,
+                            _ => Some(PcrMismatch {
+                                index,
+                                expected: expected.to_ascii_lowercase(),
+                                actual,
+                            }),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        PcrVerdict {
+            matched_release: releases
+                .iter()
+                .find(|verdict| verdict.mismatches.is_empty())
+                .map(|verdict| verdict.release.clone()),
+            releases,
+        }
+    }
+
+    fn validate(self) -> Result<Self> {
+        if self.releases.is_empty() {
+            return Err(invalid_policy("no releases listed"));
+        }
+        for release in &self.releases {
+            for (index, value) in release.expected() {
+                let valid =
+                    value.len() == PCR_LENGTH * 2 && value.bytes().all(|b| b.is_ascii_hexdigit());
+                if !valid {
+                    return Err(invalid_policy(&format!(
+                        "release {} PCR{} is not {} hex-encoded bytes",
+                        release.name, index, PCR_LENGTH
+                    )));
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// One PCR that differs from a release's expected value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PcrMismatch {
+    pub index: u16,
+    pub expected: String,
+    /// `None` when the document does not report this PCR
+    pub actual: Option<String>,
+}
+
+/// Comparison against a single release
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReleaseVerdict {
+    pub release: String,
+    pub mismatches: Vec<PcrMismatch>,
+}
+
+/// Outcome of checking PCRs against a policy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PcrVerdict {
+    /// First release whose PCRs all match
+    pub matched_release: Option<String>,
+    pub releases: Vec<ReleaseVerdict>,
+}
+
+impl PcrVerdict {
+    pub fn is_allowed(&self) -> bool {
+        self.matched_release.is_some()
+    }
+
+    /// Turn a rejected verdict into an `AttestationError`
+    pub fn into_result(self) -> Result<Self> {
+        if self.is_allowed() {
+            Ok(self)
+        } else {
+            Err(EnclaveError::AttestationError(format!(
+                "PCR verification failed: {}",
+                self
+            )))
+        }
+    }
+}
+
+impl fmt::Display for PcrVerdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(release) = &self.matched_release {
+            return write!(f, "PCRs match release {}", release);
+        }
+        write!(f, "no allowed release matches")?;
+        for verdict in &self.releases {
+            let mismatched: Vec<String> = verdict
+                .mismatches
+                .iter()
+                .map(|m| match &m.actual {
+                    Some(actual) => {
+                        format!("PCR{} is {} (expected {})", m.index, actual, m.expected)
+                    }
+                    None => format!("PCR{} missing", m.index),
+                })
+                .collect();
+            write!(f, "; {}: {}", verdict.release, mismatched.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn invalid_policy(reason: &str) -> EnclaveError {
+    EnclaveError::AttestationError(format!("Invalid PCR policy: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pcr(byte: u8) -> String {
+        hex::encode([byte; PCR_LENGTH])
+    }
+
+    fn policy_toml() -> String {
+        format!(
+            r#"
+            [[release]]
+            name = "v1.0.0"
+            pcr0 = "{}"
+            pcr1 = "{}"
+            pcr2 = "{}"
+
+            [[release]]
+            name = "v1.1.0"
+            pcr0 = "{}"
+            pcr1 = "{}"
+            pcr2 = "{}"
+            pcr8 = "{}"
+            "#,
+            pcr(0xA0),
+            pcr(0x01),
+            pcr(0x02),
+            pcr(0xB0),
+            pcr(0x01),
+            pcr(0x02),
+            pcr(0x08),
+        )
+    }
+
+    fn measured(pcr0: u8, pcr8: Option<u8>) -> BTreeMap<u16, Vec<u8>> {
+        let mut pcrs: BTreeMap<u16, Vec<u8>> = [(0, pcr0), (1, 0x01), (2, 0x02)]
+            .into_iter()
+            .map(|(index, byte)| (index, vec![byte; PCR_LENGTH]))
+            .collect();
+        if let Some(byte) = pcr8 {
+            pcrs.insert(8, vec![byte; PCR_LENGTH]);
+        }
+        pcrs
+    }
+
+    #[test]
+    fn test_any_allowed_release_matches() {
+        let policy = PcrPolicy::from_toml_str(&policy_toml()).unwrap();
+
+        let verdict = policy.verify(&measured(0xA0, None));
+        assert_eq!(verdict.matched_release.as_deref(), Some("v1.0.0"));
+
+        let verdict = policy.verify(&measured(0xB0, Some(0x08)));
+        assert_eq!(verdict.matched_release.as_deref(), Some("v1.1.0"));
+        assert!(verdict.into_result().is_ok());
+    }
+
+    #[test]
+    fn test_verdict_names_mismatched_pcrs() {
+        let policy = PcrPolicy::from_toml_str(&policy_toml()).unwrap();
+        let verdict = policy.verify(&measured(0xB0, None));
+
+        assert!(!verdict.is_allowed());
+        assert_eq!(verdict.releases[0].mismatches.len(), 1);
+        assert_eq!(verdict.releases[0].mismatches[0].index, 0);
+        assert_eq!(verdict.releases[0].mismatches[0].actual, Some(pcr(0xB0)));
+        assert_eq!(verdict.releases[1].mismatches[0].index, 8);
+        assert_eq!(verdict.releases[1].mismatches[0].actual, None);
+
+        let message = verdict.into_result().unwrap_err().to_string();
+        assert!(message.contains("v1.0.0: PCR0 is"));
+        assert!(message.contains("v1.1.0: PCR8 missing"));
+    }
+
+    #[test]
+    fn test_json_policy_and_validation() {
+        let json = serde_json::json!({
+            "release": [{ "name": "v1", "pcr0": pcr(0), "pcr1": pcr(1), "pcr2": pcr(2) }]
+        });
+        let policy = PcrPolicy::from_json_str(&json.to_string()).unwrap();
+        assert_eq!(policy.releases[0].pcr8, None);
+
+        assert!(PcrPolicy::from_toml_str("release = []").is_err());
+        let short = policy_toml().replace(&pcr(0x02), "abcd");
+        assert!(PcrPolicy::from_toml_str(&short).is_err());
+    }
+
+    #[test]
+    fn test_debug_releases() {
+        let policy = PcrPolicy::from_toml_str(&policy_toml()).unwrap();
+        assert!(policy.releases.iter().all(|release| !release.is_debug()));
+
+        let dev = PcrPolicy::load(
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("configs/pcr_policy.dev.toml"),
+        )
+        .unwrap();
+        assert!(dev.releases.iter().all(ReleasePcrs::is_debug));
+    }
+}
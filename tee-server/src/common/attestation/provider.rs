@@ -0,0 +1,126 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Attestation providers for the supported confidential-computing platforms
+//!
+//! The same binary runs on Nitro Enclaves, SEV-SNP and TDX guests; the
+//! platform is picked at startup with `ATTESTATION_PROVIDER`.
+
+use crate::common::attestation::{AttestationRequest, MockNsm, NitroNsm, TsmProvider};
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Platform that produced an attestation, telling clients how to verify it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProviderKind {
+    /// AWS Nitro NSM: COSE_Sign1 document chained to the AWS Nitro root
+    #[default]
+    Nitro,
+    /// AMD SEV-SNP attestation report, signed by the VCEK
+    SevSnp,
+    /// Intel TDX quote
+    Tdx,
+    /// Nitro-format document self-signed by a generated CA, for development
+    Software,
+}
+
+impl ProviderKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProviderKind::Nitro => "nitro",
+            ProviderKind::SevSnp => "sev-snp",
+            ProviderKind::Tdx => "tdx",
+            ProviderKind::Software => "software",
+        }
+    }
+
+    /// Whether documents are Nitro COSE_Sign1 documents carrying PCRs
+    pub fn has_pcrs(&self) -> bool {
+        matches!(self, ProviderKind::Nitro | ProviderKind::Software)
+    }
+
+    /// Instantiate the provider for this platform
+    pub fn create(self) -> Result<Arc<dyn AttestationProvider>> {
+        Ok(match self {
+            ProviderKind::Nitro => Arc::new(NitroNsm::new()),
+            ProviderKind::SevSnp => Arc::new(TsmProvider::sev_snp()),
+            ProviderKind::Tdx => Arc::new(TsmProvider::tdx()),
+            ProviderKind::Software => Arc::new(MockNsm::new()?),
+        })
+    }
+}
+
+impl FromStr for ProviderKind {
+    type Err = EnclaveError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "nitro" => Ok(ProviderKind::Nitro),
+            "sev-snp" | "snp" => Ok(ProviderKind::SevSnp),
+            "tdx" => Ok(ProviderKind::Tdx),
+            "software" => Ok(ProviderKind::Software),
+            other => Err(EnclaveError::AttestationError(format!(
+                "Unknown attestation provider {:?} (expected nitro, sev-snp, tdx or software)",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Source of attestation evidence binding `user_data` and the nonce
+pub trait AttestationProvider: Send + Sync {
+    fn kind(&self) -> ProviderKind;
+
+    /// Return the platform's evidence: a COSE_Sign1 document for Nitro and
+    /// software, the raw report or quote for SEV-SNP and TDX
+    fn attest(&self, request: &AttestationRequest) -> Result<Vec<u8>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_kind_parsing() {
+        assert_eq!(
+            "nitro".parse::<ProviderKind>().unwrap(),
+            ProviderKind::Nitro
+        );
+        assert_eq!(
+            "SEV-SNP".parse::<ProviderKind>().unwrap(),
+            ProviderKind::SevSnp
+        );
+        assert_eq!("tdx".parse::<ProviderKind>().unwrap(), ProviderKind::Tdx);
+        assert!("sgx".parse::<ProviderKind>().is_err());
+
+        for kind in [
+            ProviderKind::Nitro,
+            ProviderKind::SevSnp,
+            ProviderKind::Tdx,
+            ProviderKind::Software,
+        ] {
+            assert_eq!(kind.as_str().parse::<ProviderKind>().unwrap(), kind);
+            assert_eq!(
+                serde_json::to_value(kind).unwrap(),
+                serde_json::Value::String(kind.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_software_provider_is_tagged() {
+        let provider = ProviderKind::Software.create().unwrap();
+        assert_eq!(provider.kind(), ProviderKind::Software);
+        assert!(provider.attest(&AttestationRequest::default()).is_ok());
+    }
+}
@@ -0,0 +1,173 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! SEV-SNP and TDX attestation through the Linux configfs-tsm interface
+//!
+//! Both guest drivers expose report generation under
+//! `/sys/kernel/config/tsm/report`: create an entry, write 64 bytes of
+//! report data to `inblob`, then read the signed report (SEV-SNP) or
+//! quote (TDX) from `outblob`.
+
+use crate::common::attestation::{AttestationProvider, AttestationRequest, ProviderKind};
+use crate::{EnclaveError, Result};
+use sha2::{Digest, Sha512};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tracing::debug;
+
+/// configfs-tsm report directory
+pub const TSM_REPORT_PATH: &str = "/sys/kernel/config/tsm/report";
+
+/// Attestation via configfs-tsm on a SEV-SNP or TDX guest
+pub struct TsmProvider {
+    kind: ProviderKind,
+    root: PathBuf,
+}
+
+impl TsmProvider {
+    pub fn sev_snp() -> Self {
+        Self {
+            kind: ProviderKind::SevSnp,
+            root: PathBuf::from(TSM_REPORT_PATH),
+        }
+    }
+
+    pub fn tdx() -> Self {
+        Self {
+            kind: ProviderKind::Tdx,
+            root: PathBuf::from(TSM_REPORT_PATH),
+        }
+    }
+
+    /// Use a different configfs-tsm report directory
+    pub fn with_path(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Name the guest driver reports in the entry's `provider` file
+    fn driver(&self) -> &'static str {
+        match self.kind {
+            ProviderKind::Tdx => "tdx_guest",
+            _ => "sev_guest",
+        }
+    }
+
+    fn generate(&self, entry: &Path, data: &[u8; 64]) -> Result<Vec<u8>> {
+        std::fs::write(entry.join("inblob"), data).map_err(tsm_err)?;
+
+        let driver = std::fs::read_to_string(entry.join("provider")).map_err(tsm_err)?;
+        if driver.trim() != self.driver() {
+            return Err(EnclaveError::AttestationError(format!(
+                "configfs-tsm provider is {}, expected {}",
+                driver.trim(),
+                self.driver()
+            )));
+        }
+
+        std::fs::read(entry.join("outblob")).map_err(tsm_err)
+    }
+}
+
+impl AttestationProvider for TsmProvider {
+    fn kind(&self) -> ProviderKind {
+        self.kind
+    }
+
+    fn attest(&self, request: &AttestationRequest) -> Result<Vec<u8>> {
+        static NEXT_ENTRY: AtomicU64 = AtomicU64::new(0);
+
+        let entry = self.root.join(format!(
+            "synapsemodel-{}-{}",
+            std::process::id(),
+            NEXT_ENTRY.fetch_add(1, Ordering::Relaxed)
+        ));
+        debug!("Requesting {} report via {:?}", self.kind, entry);
+
+        std::fs::create_dir(&entry).map_err(tsm_err)?;
+        let report = self.generate(&entry, &report_data(request));
+        let _ = std::fs::remove_dir(&entry);
+        report
+    }
+}
+
+/// 64-byte report data committing to an attestation request
+///
+/// SHA-512 over each of `user_data`, `nonce` and `public_key` in order,
+/// encoded as `0x00` when absent or `0x01 || u32_be(len) || bytes`.
+pub fn report_data(request: &AttestationRequest) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+    for field in [&request.user_data, &request.nonce, &request.public_key] {
+        match field {
+            Some(bytes) => {
+                hasher.update([1u8]);
+                hasher.update((bytes.len() as u32).to_be_bytes());
+                hasher.update(bytes);
+            }
+            None => hasher.update([0u8]),
+        }
+    }
+    hasher.finalize().into()
+}
+
+fn tsm_err(e: std::io::Error) -> EnclaveError {
+    EnclaveError::AttestationError(format!("configfs-tsm report failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_data_binds_every_field() {
+        let request = AttestationRequest {
+            user_data: Some(b"key".to_vec()),
+            nonce: Some(b"nonce".to_vec()),
+            public_key: None,
+        };
+        assert_eq!(report_data(&request), report_data(&request.clone()));
+
+        let moved = AttestationRequest {
+            user_data: Some(b"keynonce".to_vec()),
+            nonce: None,
+            public_key: None,
+        };
+        let empty_nonce = AttestationRequest {
+            nonce: Some(Vec::new()),
+            ..moved.clone()
+        };
+        assert_ne!(report_data(&request), report_data(&moved));
+        assert_ne!(report_data(&moved), report_data(&empty_nonce));
+    }
+
+    #[test]
+    fn test_missing_configfs_is_an_error() {
+        let provider = TsmProvider::tdx().with_path("/nonexistent/tsm/report");
+        assert_eq!(provider.kind(), ProviderKind::Tdx);
+        assert!(provider.attest(&AttestationRequest::default()).is_err());
+    }
+
+    #[test]
+    fn test_generate_reads_the_report() {
+        let root = std::env::temp_dir().join(format!("tsm-report-{}", std::process::id()));
+        let entry = root.join("entry");
+        std::fs::create_dir_all(&entry).unwrap();
+        // What the guest driver shows in a new entry
+        std::fs::write(entry.join("provider"), "sev_guest\n").unwrap();
+        std::fs::write(entry.join("outblob"), b"signed report").unwrap();
+
+        let request = AttestationRequest {
+            user_data: Some(b"key".to_vec()),
+            ..Default::default()
+        };
+        let data = report_data(&request);
+        let snp = TsmProvider::sev_snp().with_path(&root);
+        assert_eq!(snp.generate(&entry, &data).unwrap(), b"signed report");
+        assert_eq!(std::fs::read(entry.join("inblob")).unwrap(), data);
+
+        let tdx = TsmProvider::tdx().with_path(&root);
+        let err = tdx.generate(&entry, &data).unwrap_err();
+        assert!(err.to_string().contains("expected tdx_guest"));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,370 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Verification of Nitro attestation documents
+//!
+//! Checks the COSE_Sign1 ES384 signature with the enclave certificate, then
+//! walks the cabundle from the trusted root down to that certificate.
+
+use crate::common::attestation::document::{AttestationDocument, CoseSign1, COSE_ALG_ES384};
+use crate::common::compute_hash;
+use crate::{EnclaveError, Result};
+use p384::ecdsa::signature::Verifier;
+use p384::ecdsa::{Signature, VerifyingKey};
+use std::path::Path;
+use std::time::Duration;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::prelude::{ASN1Time, FromDer};
+
+/// SHA-256 fingerprint of the AWS Nitro Enclaves root certificate (G1),
+/// as published in the AWS Nitro Enclaves documentation
+pub const AWS_NITRO_ROOT_SHA256: &str =
+    "641a0321a3e244efe456463195d606317ed7cdcc3c1756e09893f3c68f79bb5b";
+
+/// Default age after which `verify_challenge` rejects a document
+pub const DEFAULT_MAX_ATTESTATION_AGE: Duration = Duration::from_secs(300);
+
+/// Default amount a document may be dated ahead of the verifier's clock
+pub const DEFAULT_MAX_CLOCK_SKEW: Duration = Duration::from_secs(30);
+
+/// Root the cabundle must start from
+#[derive(Debug, Clone)]
+pub enum TrustAnchor {
+    /// Exact DER bytes of the root certificate
+    Certificate(Vec<u8>),
+    /// SHA-256 of the root certificate's DER bytes
+    Fingerprint([u8; 32]),
+}
+
+impl TrustAnchor {
+    fn matches(&self, root_der: &[u8]) -> bool {
+        match self {
+            TrustAnchor::Certificate(der) => der.as_slice() == root_der,
+            TrustAnchor::Fingerprint(fingerprint) => compute_hash(root_der) == fingerprint,
+        }
+    }
+}
+
+/// Verifies attestation documents against a configurable root
+#[derive(Debug, Clone)]
+pub struct AttestationVerifier {
+    anchor: TrustAnchor,
+    max_age: Duration,
+    max_skew: Duration,
+}
+
+impl AttestationVerifier {
+    pub fn new(anchor: TrustAnchor) -> Self {
+        Self {
+            anchor,
+            max_age: DEFAULT_MAX_ATTESTATION_AGE,
+            max_skew: DEFAULT_MAX_CLOCK_SKEW,
+        }
+    }
+
+    /// Trust the production AWS Nitro root
+    pub fn aws_nitro() -> Self {
+        let mut fingerprint = [0u8; 32];
+        hex::decode_to_slice(AWS_NITRO_ROOT_SHA256, &mut fingerprint)
+            .expect("AWS Nitro root fingerprint is valid hex");
+        Self::new(TrustAnchor::Fingerprint(fingerprint))
+    }
+
+    /// Trust a specific root certificate (DER), e.g. `MockNsm::root_certificate_der`
+    pub fn with_root_certificate(der: impl Into<Vec<u8>>) -> Self {
+        Self::new(TrustAnchor::Certificate(der.into()))
+    }
+
+    /// Load the root certificate from a PEM or DER file
+    pub fn from_root_file(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = std::fs::read(path.as_ref()).map_err(|e| {
+            EnclaveError::AttestationError(format!(
+                "Failed to read root certificate {:?}: {}",
+                path.as_ref(),
+                e
+            ))
+        })?;
+        let der = match x509_parser::pem::parse_x509_pem(&bytes) {
+            Ok((_, pem)) => pem.contents,
+            Err(_) => bytes,
+        };
+        Ok(Self::with_root_certificate(der))
+    }
+
+    /// Maximum document age accepted by `verify_challenge`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// How far ahead of `now_ms` a document's timestamp may be in `verify_challenge`
+    pub fn with_max_clock_skew(mut self, max_skew: Duration) -> Self {
+        self.max_skew = max_skew;
+        self
+    }
+
+    /// Verify a document requested with `nonce` and no older than the max age
+    ///
+    /// `now_ms` is the verifier's clock in milliseconds since the UNIX epoch.
+    pub fn verify_challenge(
+        &self,
+        document: &[u8],
+        nonce: &[u8],
+        now_ms: u64,
+    ) -> Result<AttestationDocument> {
+        let doc = self.verify(document)?;
+        doc.check_challenge(nonce, self.max_age, self.max_skew, now_ms)?;
+        Ok(doc)
+    }
+
+    /// Verify a COSE_Sign1 attestation document and return its contents
+    pub fn verify(&self, document: &[u8]) -> Result<AttestationDocument> {
+        let cose = CoseSign1::from_cbor(document)?;
+        if cose.algorithm()? != COSE_ALG_ES384 {
+            return Err(invalid("document is not signed with ES384"));
+        }
+
+        let doc = AttestationDocument::from_cbor(&cose.payload)?;
+        if doc.digest != "SHA384" {
+            return Err(invalid(&format!("unsupported PCR digest {}", doc.digest)));
+        }
+
+        let leaf = self.verify_chain(&doc)?;
+
+        let verifying_key =
+            VerifyingKey::from_sec1_bytes(&leaf.public_key().subject_public_key.data)
+                .map_err(|_| invalid("enclave certificate does not hold a P-384 key"))?;
+        let signature = Signature::from_slice(&cose.signature)
+            .map_err(|_| invalid("malformed ES384 signature"))?;
+        verifying_key
+            .verify(
+                &CoseSign1::sig_structure(&cose.protected, &cose.payload),
+                &signature,
+            )
+            .map_err(|_| invalid("COSE signature does not match the enclave certificate"))?;
+
+        Ok(doc)
+    }
+
+    /// Check `cabundle[0]` is trusted and each certificate signs the next,
+    /// ending at the enclave certificate; returns the parsed enclave certificate
+    fn verify_chain<'a>(&self, doc: &'a AttestationDocument) -> Result<X509Certificate<'a>> {
+        let root_der = doc
+            .cabundle
+            .first()
+            .ok_or_else(|| invalid("empty cabundle"))?;
+        if !self.anchor.matches(root_der) {
+            return Err(invalid("cabundle root is not the trusted root"));
+        }
+
+        let at = ASN1Time::from_timestamp((doc.timestamp / 1000) as i64)
+            .map_err(|_| invalid("document timestamp out of range"))?;
+
+        let chain = doc
+            .cabundle
+            .iter()
+            .chain(std::iter::once(&doc.certificate))
+            .map(|der| {
+                X509Certificate::from_der(der)
+                    .map(|(_, cert)| cert)
+                    .map_err(|e| invalid(&format!("unparseable certificate: {}", e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        chain[0]
+            .verify_signature(None)
+            .map_err(|_| invalid("root certificate is not self-signed"))?;
+
+        for (depth, cert) in chain.iter().enumerate() {
+            if !cert.validity().is_valid_at(at) {
+                return Err(invalid(&format!(
+                    "certificate at depth {} not valid at document time",
+                    depth
+                )));
+            }
+            if depth == 0 {
+                continue;
+            }
+
+            let issuer = &chain[depth - 1];
+            if !issuer.is_ca() {
+                return Err(invalid(&format!(
+                    "certificate at depth {} is not a CA",
+                    depth - 1
+                )));
+            }
+            if cert.issuer() != issuer.subject() {
+                return Err(invalid(&format!("issuer mismatch at depth {}", depth)));
+            }
+            cert.verify_signature(Some(issuer.public_key()))
+                .map_err(|_| invalid(&format!("bad certificate signature at depth {}", depth)))?;
+        }
+
+        Ok(chain
+            .into_iter()
+            .last()
+            .expect("chain holds at least the enclave certificate"))
+    }
+}
+
+impl AttestationDocument {
+    /// Whether `user_data` is the given signing public key (as from `/get_pk`)
+    /// followed by a 32-byte models root, as built by `attestation_user_data`
+    pub fn binds_public_key(&self, public_key: &[u8]) -> bool {
+        !public_key.is_empty()
+            && self.user_data.as_deref().is_some_and(|data| {
+                data.len() == public_key.len() + 32 && data.starts_with(public_key)
+            })
+    }
+
+    /// Whether `user_data` ends with the given models Merkle root (as from `/get_measurements`)
+    pub fn binds_models_root(&self, models_root: &[u8; 32]) -> bool {
+        self.user_data
+            .as_deref()
+            .is_some_and(|data| data.len() > models_root.len() && data.ends_with(models_root))
+    }
+
+    /// Check the document answers `nonce`, is at most `max_age` old and is
+    /// dated no more than `max_skew` after `now_ms`
+    pub fn check_challenge(
+        &self,
+        nonce: &[u8],
+        max_age: Duration,
+        max_skew: Duration,
+        now_ms: u64,
+    ) -> Result<()> {
+        if self.nonce.as_deref() != Some(nonce) {
+            return Err(invalid("nonce does not match the challenge"));
+        }
+        if self.timestamp > now_ms.saturating_add(max_skew.as_millis() as u64) {
+            return Err(invalid(&format!(
+                "document is dated {} ms in the future (max skew {} ms)",
+                self.timestamp - now_ms,
+                max_skew.as_millis()
+            )));
+        }
+        let age_ms = now_ms.saturating_sub(self.timestamp);
+        if age_ms > max_age.as_millis() as u64 {
+            return Err(invalid(&format!(
+                "document is {} ms old (max {} ms)",
+                age_ms,
+                max_age.as_millis()
+            )));
+        }
+        Ok(())
+    }
+}
+
+fn invalid(reason: &str) -> EnclaveError {
+    EnclaveError::AttestationError(format!("Attestation verification failed: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::attestation::{
+        attestation_user_data, AttestationProvider, AttestationRequest, MockNsm,
+    };
+
+    const FIXTURE_DOCUMENT: &[u8] =
+        include_bytes!("../../../tests/fixtures/attestation/mock_document.cbor");
+    const FIXTURE_ROOT: &[u8] = include_bytes!("../../../tests/fixtures/attestation/mock_root.der");
+
+    #[test]
+    fn test_mock_document_verifies_against_test_root() {
+        let nsm = MockNsm::new().unwrap().with_pcr(2, vec![0x22; 48]);
+        let bytes = nsm
+            .attest(&AttestationRequest {
+                user_data: Some(attestation_user_data(&[9; 32], &[7; 32])),
+                nonce: Some(b"challenge".to_vec()),
+                public_key: None,
+            })
+            .unwrap();
+
+        let doc = AttestationVerifier::with_root_certificate(nsm.root_certificate_der())
+            .verify(&bytes)
+            .unwrap();
+        assert_eq!(doc.pcrs.get(&2), Some(&vec![0x22; 48]));
+        assert_eq!(doc.nonce.as_deref(), Some(&b"challenge"[..]));
+        assert!(doc.binds_public_key(&[9; 32]));
+        assert!(!doc.binds_public_key(&[8; 32]));
+        // Only the whole key counts, not a prefix of it or of the user data
+        assert!(!doc.binds_public_key(&[]));
+        assert!(!doc.binds_public_key(&[9; 16]));
+        assert!(!doc.binds_public_key(&[[9; 32], [7; 32]].concat()));
+        assert!(doc.binds_models_root(&[7; 32]));
+    }
+
+    #[test]
+    fn test_untrusted_root_and_tampering_rejected() {
+        let nsm = MockNsm::new().unwrap();
+        let bytes = nsm.attest(&AttestationRequest::default()).unwrap();
+
+        let other = MockNsm::new().unwrap();
+        assert!(
+            AttestationVerifier::with_root_certificate(other.root_certificate_der())
+                .verify(&bytes)
+                .is_err()
+        );
+        assert!(AttestationVerifier::aws_nitro().verify(&bytes).is_err());
+
+        let mut cose = CoseSign1::from_cbor(&bytes).unwrap();
+        let mut doc = AttestationDocument::from_cbor(&cose.payload).unwrap();
+        doc.user_data = Some(vec![1; 32]);
+        cose.payload = doc.to_cbor();
+        let err = AttestationVerifier::with_root_certificate(nsm.root_certificate_der())
+            .verify(&cose.to_cbor())
+            .unwrap_err();
+        assert!(err.to_string().contains("COSE signature"));
+    }
+
+    #[test]
+    fn test_challenge_nonce_and_freshness() {
+        let nsm = MockNsm::new().unwrap();
+        let bytes = nsm
+            .attest(&AttestationRequest {
+                nonce: Some(b"session-1".to_vec()),
+                ..Default::default()
+            })
+            .unwrap();
+        let verifier = AttestationVerifier::with_root_certificate(nsm.root_certificate_der())
+            .with_max_age(Duration::from_secs(60));
+        let timestamp = AttestationDocument::from_cose_sign1(&bytes)
+            .unwrap()
+            .timestamp;
+
+        assert!(verifier
+            .verify_challenge(&bytes, b"session-1", timestamp + 1_000)
+            .is_ok());
+
+        let err = verifier
+            .verify_challenge(&bytes, b"session-2", timestamp)
+            .unwrap_err();
+        assert!(err.to_string().contains("nonce"));
+
+        let err = verifier
+            .verify_challenge(&bytes, b"session-1", timestamp + 60_001)
+            .unwrap_err();
+        assert!(err.to_string().contains("ms old"));
+
+        // A little skew is tolerated, a document from the future is not
+        assert!(verifier
+            .verify_challenge(&bytes, b"session-1", timestamp - 1_000)
+            .is_ok());
+        let err = verifier
+            .verify_challenge(&bytes, b"session-1", timestamp - 30_001)
+            .unwrap_err();
+        assert!(err.to_string().contains("in the future"));
+    }
+
+    #[test]
+    fn test_fixture_document() {
+        let doc = AttestationVerifier::with_root_certificate(FIXTURE_ROOT)
+            .verify(FIXTURE_DOCUMENT)
+            .unwrap();
+        assert_eq!(doc.module_id, "i-mock-enc-0000000000000000");
+        assert_eq!(doc.pcrs.len(), 16);
+        assert_eq!(doc.user_data.as_deref(), Some(&b"fixture-signing-key"[..]));
+        assert_eq!(doc.nonce.as_deref(), Some(&b"fixture-nonce"[..]));
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! RFC 8785 JSON Canonicalization Scheme (JCS)
+//!
+//! Produces a byte-for-byte stable encoding of a JSON value so that the
+//! Python backend, the TS frontend and the enclave hash identical bytes.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+/// Canonicalize a JSON value into its RFC 8785 UTF-8 byte form
+pub fn canonicalize(value: &Value) -> Vec<u8> {
+    to_canonical_string(value).into_bytes()
+}
+
+/// Canonicalize a JSON value into its RFC 8785 string form
+pub fn to_canonical_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value);
+    out
+}
+
+fn write_value(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => write_number(out, n),
+        Value::String(s) => write_string(out, s),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            // Properties are sorted by their UTF-16 code units (RFC 8785 §3.2.3)
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+
+            out.push('{');
+            for (i, (key, val)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(out, key);
+                out.push(':');
+                write_value(out, val);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Numbers are serialized as IEEE-754 doubles using the ECMAScript
+/// `Number.prototype.toString` algorithm (RFC 8785 §3.2.2.3)
+fn write_number(out: &mut String, n: &serde_json::Number) {
+    let f = n.as_f64().unwrap_or(0.0);
+    if f == 0.0 {
+        // Covers -0.0, which ECMAScript renders as "0"
+        out.push('0');
+        return;
+    }
+    let mut buffer = ryu_js::Buffer::new();
+    out.push_str(buffer.format_finite(f));
+}
+
+/// Strings use the minimal JSON escaping required by RFC 8785 §3.2.2.2
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{09}' => out.push_str("\\t"),
+            '\u{0A}' => out.push_str("\\n"),
+            '\u{0C}' => out.push_str("\\f"),
+            '\u{0D}' => out.push_str("\\r"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Serde adapter for `serde_json::Value` fields inside signed messages
+///
+/// BCS has no float or map types, so binary formats receive the canonical
+/// JSON bytes (a Move `vector<u8>`), while JSON keeps the plain value.
+pub mod bcs_json {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Value, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            value.serialize(serializer)
+        } else {
+            serializer.serialize_bytes(&canonicalize(value))
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Value, D::Error> {
+        if deserializer.is_human_readable() {
+            Value::deserialize(deserializer)
+        } else {
+            let bytes = Vec::<u8>::deserialize(deserializer)?;
+            serde_json::from_slice(&bytes).map_err(serde::de::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_key_order_and_whitespace_independent() {
+        let a: Value =
+            serde_json::from_str(r#"{"b": 1, "a": [1, 2, {"d": true, "c": null}]}"#).unwrap();
+        let b: Value = serde_json::from_str(r#"{"a":[1,2,{"c":null,"d":true}],"b":1}"#).unwrap();
+
+        assert_eq!(canonicalize(&a), canonicalize(&b));
+        assert_eq!(
+            to_canonical_string(&a),
+            r#"{"a":[1,2,{"c":null,"d":true}],"b":1}"#
+        );
+    }
+
+    #[test]
+    fn test_number_formatting() {
+        let value: Value =
+            serde_json::from_str("[1.0, 1e2, -0.0, 0.5, 1e21, 1e-7, 333333333.33333329]").unwrap();
+        assert_eq!(
+            to_canonical_string(&value),
+            "[1,100,0,0.5,1e+21,1e-7,333333333.3333333]"
+        );
+    }
+
+    #[test]
+    fn test_utf16_key_sorting() {
+        // RFC 8785 §3.2.3 sorting example
+        let value = json!({
+            "\u{20ac}": "Euro Sign",
+            "\r": "Carriage Return",
+            "\u{fb33}": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "\u{1f600}": "Emoji: Grinning Face",
+            "\u{0080}": "Control",
+            "\u{00f6}": "Latin Small Letter O With Diaeresis"
+        });
+        assert_eq!(
+            to_canonical_string(&value),
+            concat!(
+                "{\"\\r\":\"Carriage Return\",",
+                "\"1\":\"One\",",
+                "\"\u{0080}\":\"Control\",",
+                "\"\u{00f6}\":\"Latin Small Letter O With Diaeresis\",",
+                "\"\u{20ac}\":\"Euro Sign\",",
+                "\"\u{1f600}\":\"Emoji: Grinning Face\",",
+                "\"\u{fb33}\":\"Hebrew Letter Dalet With Dagesh\"}"
+            )
+        );
+    }
+
+    #[test]
+    fn test_string_escaping() {
+        let value = json!("\u{0001}\"\\\n/é");
+        assert_eq!(to_canonical_string(&value), "\"\\u0001\\\"\\\\\\n/é\"");
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Persistent signing identity with key rotation epochs
+//!
+//! The current Ed25519 key and the public halves of every retired key are
+//! kept together. When a storage path is configured the whole keystore is
+//! sealed with AES-256-GCM so the identity survives restarts.
+
+use crate::common::signing::key_id;
+use crate::{EnclaveError, Result};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use fastcrypto::ed25519::Ed25519KeyPair;
+use fastcrypto::traits::{KeyPair, ToFromBytes};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Additional authenticated data bound into every sealed keystore
+const SEALING_AAD: &[u8] = b"synapsemodel-keystore-v1";
+
+/// Validity window of one signing key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyEpoch {
+    pub epoch: u64,
+    pub key_id: String,
+    /// Hex-encoded Ed25519 public key
+    pub public_key: String,
+    pub not_before_ms: u64,
+    /// `None` while the key is current
+    pub not_after_ms: Option<u64>,
+}
+
+impl KeyEpoch {
+    fn new(epoch: u64, keypair: &Ed25519KeyPair, not_before_ms: u64) -> Self {
+        Self {
+            epoch,
+            key_id: key_id(keypair.public()),
+            public_key: hex::encode(keypair.public().as_bytes()),
+            not_before_ms,
+            not_after_ms: None,
+        }
+    }
+
+    /// Whether a response signed at `timestamp_ms` falls inside this epoch
+    pub fn covers(&self, timestamp_ms: u64) -> bool {
+        let before_end = match self.not_after_ms {
+            Some(end) => timestamp_ms <= end,
+            None => true,
+        };
+        timestamp_ms >= self.not_before_ms && before_end
+    }
+}
+
+/// Location and sealing key for the on-disk keystore
+#[derive(Clone)]
+pub struct SealedStorage {
+    pub path: PathBuf,
+    sealing_key: [u8; 32],
+}
+
+impl SealedStorage {
+    pub fn new(path: impl Into<PathBuf>, sealing_key: [u8; 32]) -> Self {
+        Self {
+            path: path.into(),
+            sealing_key,
+        }
+    }
+
+    fn seal(&self, plaintext: &[u8]) -> Result<SealedFile> {
+        let cipher = Aes256Gcm::new_from_slice(&self.sealing_key)
+            .map_err(|e| EnclaveError::CryptoError(format!("Invalid sealing key: {}", e)))?;
+        let nonce: [u8; 12] = rand::random();
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: SEALING_AAD,
+                },
+            )
+            .map_err(|e| EnclaveError::CryptoError(format!("Failed to seal keystore: {}", e)))?;
+
+        Ok(SealedFile {
+            version: 1,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    fn unseal(&self, sealed: &SealedFile) -> Result<Vec<u8>> {
+        if sealed.version != 1 {
+            return Err(EnclaveError::CryptoError(format!(
+                "Unsupported keystore version: {}",
+                sealed.version
+            )));
+        }
+        let decode = |field: &str| {
+            hex::decode(field)
+                .map_err(|e| EnclaveError::CryptoError(format!("Corrupt keystore: {}", e)))
+        };
+        let nonce = decode(&sealed.nonce)?;
+        if nonce.len() != 12 {
+            return Err(EnclaveError::CryptoError(
+                "Corrupt keystore: bad nonce".to_string(),
+            ));
+        }
+        let ciphertext = decode(&sealed.ciphertext)?;
+
+        let cipher = Aes256Gcm::new_from_slice(&self.sealing_key)
+            .map_err(|e| EnclaveError::CryptoError(format!("Invalid sealing key: {}", e)))?;
+        cipher
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: SEALING_AAD,
+                },
+            )
+            .map_err(|_| EnclaveError::CryptoError("Failed to unseal keystore".to_string()))
+    }
+}
+
+/// On-disk sealed keystore file
+#[derive(Serialize, Deserialize)]
+struct SealedFile {
+    version: u8,
+    nonce: String,
+    ciphertext: String,
+}
+
+/// Plaintext contents of a sealed keystore
+#[derive(Serialize, Deserialize)]
+struct KeystoreContents {
+    private_key: String,
+    epochs: Vec<KeyEpoch>,
+}
+
+/// Signing keys and their rotation epochs
+pub struct Keystore {
+    current: Ed25519KeyPair,
+    epochs: Vec<KeyEpoch>,
+    rotation_interval_ms: Option<u64>,
+    storage: Option<SealedStorage>,
+}
+
+impl Keystore {
+    /// In-memory keystore holding a single key, lost on restart
+    pub fn ephemeral(keypair: Ed25519KeyPair, now_ms: u64) -> Self {
+        let epochs = vec![KeyEpoch::new(0, &keypair, now_ms)];
+        Self {
+            current: keypair,
+            epochs,
+            rotation_interval_ms: None,
+            storage: None,
+        }
+    }
+
+    /// Unseal the keystore at `storage.path`, or create and seal a new one
+    pub fn load_or_create(storage: SealedStorage, now_ms: u64) -> Result<Self> {
+        if storage.path.exists() {
+            let bytes = std::fs::read(&storage.path).map_err(|e| {
+                EnclaveError::GenericError(format!("Failed to read keystore: {}", e))
+            })?;
+            let sealed: SealedFile = serde_json::from_slice(&bytes).map_err(|e| {
+                EnclaveError::SerializationError(format!("Invalid keystore file: {}", e))
+            })?;
+            let contents: KeystoreContents = serde_json::from_slice(&storage.unseal(&sealed)?)
+                .map_err(|e| {
+                    EnclaveError::SerializationError(format!("Invalid keystore contents: {}", e))
+                })?;
+
+            let private_key = hex::decode(&contents.private_key)
+                .map_err(|e| EnclaveError::CryptoError(format!("Corrupt keystore: {}", e)))?;
+            let current = Ed25519KeyPair::from_bytes(&private_key)
+                .map_err(|e| EnclaveError::CryptoError(format!("Corrupt keystore: {}", e)))?;
+
+            let current_id = key_id(current.public());
+            if contents.epochs.last().map(|e| &e.key_id) != Some(&current_id) {
+                return Err(EnclaveError::CryptoError(
+                    "Corrupt keystore: current key does not match latest epoch".to_string(),
+                ));
+            }
+
+            info!(
+                "Unsealed keystore from {:?} ({} epochs, current key {})",
+                storage.path,
+                contents.epochs.len(),
+                current_id
+            );
+
+            Ok(Self {
+                current,
+                epochs: contents.epochs,
+                rotation_interval_ms: None,
+                storage: Some(storage),
+            })
+        } else {
+            let keypair = Ed25519KeyPair::generate(&mut rand::thread_rng());
+            let mut keystore = Self::ephemeral(keypair, now_ms);
+            keystore.storage = Some(storage);
+            keystore.save()?;
+            info!("Created new sealed keystore");
+            Ok(keystore)
+        }
+    }
+
+    /// Sealed keystore at `path`, unsealed with the hex `sealing_key`, or an
+    /// ephemeral key without a path
+    pub fn open(path: Option<&Path>, sealing_key: Option<&str>, now_ms: u64) -> Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::ephemeral(
+                Ed25519KeyPair::generate(&mut rand::thread_rng()),
+                now_ms,
+            ));
+        };
+        let sealing_key = sealing_key.ok_or_else(|| {
+            EnclaveError::ValidationError(
+                "KEYSTORE_SEALING_KEY is required when KEYSTORE_PATH is set".to_string(),
+            )
+        })?;
+        Self::load_or_create(
+            SealedStorage::new(path, parse_sealing_key(sealing_key)?),
+            now_ms,
+        )
+    }
+
+    /// Rotate the signing key every `interval_ms` (`None` disables rotation)
+    pub fn with_rotation_interval(mut self, interval_ms: Option<u64>) -> Self {
+        self.rotation_interval_ms = interval_ms;
+        self
+    }
+
+    /// Current signing key
+    pub fn current(&self) -> &Ed25519KeyPair {
+        &self.current
+    }
+
+    /// Epoch of the current signing key
+    pub fn current_epoch(&self) -> &KeyEpoch {
+        self.epochs
+            .last()
+            .expect("keystore always has a current epoch")
+    }
+
+    /// All epochs, oldest first; the last one is current
+    pub fn epochs(&self) -> &[KeyEpoch] {
+        &self.epochs
+    }
+
+    /// Whether the current key has outlived the rotation interval
+    pub fn rotation_due(&self, now_ms: u64) -> bool {
+        self.rotation_interval_ms.is_some_and(|interval| {
+            now_ms >= self.current_epoch().not_before_ms.saturating_add(interval)
+        })
+    }
+
+    /// Retire the current key and start a new epoch
+    ///
+    /// The new key is only used once it is sealed; if sealing fails the
+    /// current key stays and the rotation is retried when next due.
+    pub fn rotate(&mut self, now_ms: u64) -> Result<&KeyEpoch> {
+        let next_epoch = self.current_epoch().epoch + 1;
+        let keypair = Ed25519KeyPair::generate(&mut rand::thread_rng());
+
+        let mut epochs = self.epochs.clone();
+        if let Some(previous) = epochs.last_mut() {
+            previous.not_after_ms = Some(now_ms);
+        }
+        epochs.push(KeyEpoch::new(next_epoch, &keypair, now_ms));
+        self.seal(&keypair, &epochs)?;
+        self.epochs = epochs;
+        self.current = keypair;
+
+        let epoch = self.current_epoch();
+        info!(
+            "Rotated signing key to epoch {} ({})",
+            epoch.epoch, epoch.key_id
+        );
+        Ok(epoch)
+    }
+
+    /// Rotate if the interval has elapsed; returns whether a rotation happened
+    pub fn rotate_if_due(&mut self, now_ms: u64) -> Result<bool> {
+        if self.rotation_due(now_ms) {
+            self.rotate(now_ms)?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Seal the keystore to disk, if storage is configured
+    pub fn save(&self) -> Result<()> {
+        self.seal(&self.current, &self.epochs)
+    }
+
+    fn seal(&self, current: &Ed25519KeyPair, epochs: &[KeyEpoch]) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+
+        let contents = KeystoreContents {
+            private_key: hex::encode(current.as_ref()),
+            epochs: epochs.to_vec(),
+        };
+        let plaintext = serde_json::to_vec(&contents)
+            .map_err(|e| EnclaveError::SerializationError(e.to_string()))?;
+        let sealed = serde_json::to_vec_pretty(&storage.seal(&plaintext)?)
+            .map_err(|e| EnclaveError::SerializationError(e.to_string()))?;
+
+        write_atomic(&storage.path, &sealed)
+    }
+}
+
+/// Parse a hex-encoded 32-byte sealing key
+pub fn parse_sealing_key(hex_key: &str) -> Result<[u8; 32]> {
+    hex::decode(hex_key.trim_start_matches("0x"))
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| {
+            EnclaveError::ValidationError("Sealing key must be 32 bytes of hex".to_string())
+        })
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, bytes)
+        .and_then(|_| std::fs::rename(&tmp_path, path))
+        .map_err(|e| EnclaveError::GenericError(format!("Failed to write keystore: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "synapse-keystore-{}-{}.json",
+            name,
+            std::process::id()
+        ))
+    }
+
+    #[test]
+    fn test_rotation_closes_previous_epoch() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let mut keystore = Keystore::ephemeral(kp, 1_000).with_rotation_interval(Some(500));
+
+        assert!(!keystore.rotate_if_due(1_499).unwrap());
+        assert!(keystore.rotate_if_due(1_500).unwrap());
+
+        let epochs = keystore.epochs();
+        assert_eq!(epochs.len(), 2);
+        assert_eq!(epochs[0].not_after_ms, Some(1_500));
+        assert_eq!(epochs[1].epoch, 1);
+        assert_eq!(epochs[1].not_after_ms, None);
+        assert_eq!(epochs[1].key_id, key_id(keystore.current().public()));
+        assert!(epochs[0].covers(1_200) && !epochs[0].covers(1_600));
+    }
+
+    #[test]
+    fn test_sealed_keystore_survives_reload() {
+        let path = temp_path("reload");
+        let _ = std::fs::remove_file(&path);
+        let storage = SealedStorage::new(&path, [7u8; 32]);
+
+        let mut keystore = Keystore::load_or_create(storage.clone(), 1_000).unwrap();
+        keystore.rotate(2_000).unwrap();
+        let expected = keystore.epochs().to_vec();
+
+        let reloaded = Keystore::load_or_create(storage, 3_000).unwrap();
+        assert_eq!(reloaded.epochs(), expected.as_slice());
+        assert_eq!(reloaded.current().as_ref(), keystore.current().as_ref());
+
+        // Wrong sealing key cannot open it
+        assert!(Keystore::load_or_create(SealedStorage::new(&path, [8u8; 32]), 3_000).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_failed_rotation_keeps_the_current_key() {
+        let dir = temp_path("unwritable");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("keystore.json");
+        let mut keystore = Keystore::load_or_create(SealedStorage::new(&path, [7u8; 32]), 1_000)
+            .unwrap()
+            .with_rotation_interval(Some(500));
+        let current = keystore.current().as_ref().to_vec();
+
+        // The sealed file can no longer be replaced
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(keystore.rotate_if_due(1_500).is_err());
+        assert_eq!(keystore.current().as_ref(), current.as_slice());
+        assert_eq!(keystore.epochs().len(), 1);
+        assert_eq!(keystore.epochs()[0].not_after_ms, None);
+        assert!(keystore.rotation_due(1_600));
+    }
+}
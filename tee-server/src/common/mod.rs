@@ -2,10 +2,13 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub mod attestation;
+pub mod canonical;
+pub mod keystore;
 pub mod signing;
 pub mod types;
 
 pub use attestation::*;
+pub use keystore::*;
 pub use signing::*;
 pub use types::*;
 
@@ -19,24 +22,80 @@ pub enum IntentScope {
     ParameterLoad = 1,
 }
 
+/// Intent with the same BCS layout as `sui_types::intent::Intent`
+///
+/// Encodes as three bytes: `[scope, version, app_id]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Intent {
+    pub scope: u8,
+    pub version: u8,
+    pub app_id: u8,
+}
+
+impl Intent {
+    /// Raw intent bytes as they prefix every signed message
+    pub fn to_bytes(&self) -> [u8; 3] {
+        [self.scope, self.version, self.app_id]
+    }
+}
+
+/// Configured intent version and app id used for signed responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntentConfig {
+    pub version: u8,
+    pub app_id: u8,
+}
+
+impl IntentConfig {
+    /// Build the intent for a given scope
+    pub fn intent(&self, scope: IntentScope) -> Intent {
+        Intent {
+            scope: scope as u8,
+            version: self.version,
+            app_id: self.app_id,
+        }
+    }
+}
+
+impl Default for IntentConfig {
+    /// `IntentVersion::V0` and `AppId::Sui`, as in `sui_types::intent`
+    fn default() -> Self {
+        Self {
+            version: 0,
+            app_id: 0,
+        }
+    }
+}
+
 /// Intent message wrapper for signatures
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IntentMessage<T> {
-    pub intent: u8,
+    pub intent: Intent,
     pub timestamp_ms: u64,
     pub data: T,
 }
 
 impl<T> IntentMessage<T> {
     pub fn new(data: T, timestamp_ms: u64, scope: IntentScope) -> Self {
+        Self::with_intent(data, timestamp_ms, IntentConfig::default().intent(scope))
+    }
+
+    pub fn with_intent(data: T, timestamp_ms: u64, intent: Intent) -> Self {
         Self {
-            intent: scope as u8,
+            intent,
             timestamp_ms,
             data,
         }
     }
 }
 
+impl<T: Serialize> IntentMessage<T> {
+    /// BCS bytes covered by the signature, as rebuilt by Move `ed25519::ed25519_verify`
+    pub fn to_bcs_bytes(&self) -> Result<Vec<u8>, bcs::Error> {
+        bcs::to_bytes(self)
+    }
+}
+
 /// Create signed response
 pub fn to_signed_response<T: Serialize>(
     keypair: &fastcrypto::ed25519::Ed25519KeyPair,
@@ -44,12 +103,27 @@ pub fn to_signed_response<T: Serialize>(
     timestamp_ms: u64,
     scope: IntentScope,
 ) -> ProcessedDataResponse<IntentMessage<T>> {
-    use fastcrypto::traits::Signer;
+    to_signed_response_with_intent(
+        keypair,
+        data,
+        timestamp_ms,
+        IntentConfig::default().intent(scope),
+    )
+}
+
+/// Create signed response with an explicit intent
+pub fn to_signed_response_with_intent<T: Serialize>(
+    keypair: &fastcrypto::ed25519::Ed25519KeyPair,
+    data: T,
+    timestamp_ms: u64,
+    intent: Intent,
+) -> ProcessedDataResponse<IntentMessage<T>> {
+    use fastcrypto::traits::{KeyPair, Signer};
     
-    let intent_message = IntentMessage::new(data, timestamp_ms, scope);
+    let intent_message = IntentMessage::with_intent(data, timestamp_ms, intent);
     
     // Serialize the message
-    let message_bytes = bcs::to_bytes(&intent_message).expect("BCS serialization failed");
+    let message_bytes = intent_message.to_bcs_bytes().expect("BCS serialization failed");
     
     // Sign the message
     let signature = keypair.sign(&message_bytes);
@@ -58,6 +132,7 @@ pub fn to_signed_response<T: Serialize>(
     ProcessedDataResponse {
         response: intent_message,
         signature: hex::encode(signature_bytes),
+        key_id: key_id(keypair.public()),
     }
 }
 
@@ -66,6 +141,9 @@ pub fn to_signed_response<T: Serialize>(
 pub struct ProcessedDataResponse<T> {
     pub response: T,
     pub signature: String,
+    /// Id of the signing key, see `/get_pk`
+    #[serde(default)]
+    pub key_id: String,
 }
 
 /// Generic request wrapper
@@ -73,3 +151,36 @@ pub struct ProcessedDataResponse<T> {
 pub struct ProcessDataRequest<T> {
     pub payload: T,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intent_message_bcs_layout() {
+        let intent = IntentConfig { version: 0, app_id: 3 }.intent(IntentScope::ParameterLoad);
+        let message = IntentMessage::with_intent(7u8, 1, intent);
+
+        let bytes = message.to_bcs_bytes().unwrap();
+        assert_eq!(bytes, vec![1, 0, 3, 1, 0, 0, 0, 0, 0, 0, 0, 7]);
+        assert_eq!(&bytes[..3], &intent.to_bytes());
+    }
+
+    #[test]
+    fn test_json_value_signed_as_canonical_bytes() {
+        #[derive(Serialize)]
+        struct Payload {
+            #[serde(with = "canonical::bcs_json")]
+            result: serde_json::Value,
+        }
+
+        let payload = Payload {
+            result: serde_json::json!({"confidence": 0.5, "prediction": 7}),
+        };
+        let canonical = br#"{"confidence":0.5,"prediction":7}"#;
+
+        let mut expected = vec![canonical.len() as u8];
+        expected.extend_from_slice(canonical);
+        assert_eq!(bcs::to_bytes(&payload).unwrap(), expected);
+    }
+}
@@ -1,10 +1,28 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
-use fastcrypto::ed25519::Ed25519KeyPair;
+use crate::common::canonical;
+use fastcrypto::ed25519::{Ed25519KeyPair, Ed25519PublicKey};
 use fastcrypto::traits::{KeyPair, Signer, ToFromBytes};
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 
+/// Version tag for the JSON encoding fed into signed hashes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashVersion {
+    /// `serde_json::to_vec` output (pre-JCS responses)
+    Legacy,
+    /// RFC 8785 canonical JSON
+    #[default]
+    Jcs,
+}
+
+impl HashVersion {
+    /// Hash version used for newly signed responses
+    pub const CURRENT: HashVersion = HashVersion::Jcs;
+}
+
 /// Generate new Ed25519 keypair
 pub fn generate_keypair() -> Ed25519KeyPair {
     Ed25519KeyPair::generate(&mut rand::thread_rng())
@@ -15,6 +33,11 @@ pub fn get_public_key_hex(keypair: &Ed25519KeyPair) -> String {
     hex::encode(keypair.public().as_bytes())
 }
 
+/// Short identifier for a signing key: first 8 bytes of SHA-256(public key), hex
+pub fn key_id(public_key: &Ed25519PublicKey) -> String {
+    hex::encode(&compute_hash(public_key.as_bytes())[..8])
+}
+
 /// Sign data with keypair
 pub fn sign_data(keypair: &Ed25519KeyPair, data: &[u8]) -> Vec<u8> {
     keypair.sign(data).as_ref().to_vec()
@@ -27,9 +50,49 @@ pub fn compute_hash(data: &[u8]) -> Vec<u8> {
     hasher.finalize().to_vec()
 }
 
+/// Encode a JSON value into the bytes hashed under the given version
+pub fn encode_json_for_hash(value: &serde_json::Value, version: HashVersion) -> Vec<u8> {
+    match version {
+        HashVersion::Legacy => serde_json::to_vec(value).unwrap_or_default(),
+        HashVersion::Jcs => canonical::canonicalize(value),
+    }
+}
+
+/// Compute SHA-256 hash of a JSON value under the given version
+pub fn compute_json_hash(value: &serde_json::Value, version: HashVersion) -> Vec<u8> {
+    compute_hash(&encode_json_for_hash(value, version))
+}
+
 /// Compute input hash for verification
 pub fn compute_input_hash(input_data: &serde_json::Value) -> String {
-    let input_bytes = serde_json::to_vec(input_data).unwrap_or_default();
-    let hash = compute_hash(&input_bytes);
+    compute_input_hash_versioned(input_data, HashVersion::CURRENT)
+}
+
+/// Compute input hash with an explicit version, e.g. to recompute old hashes
+pub fn compute_input_hash_versioned(input_data: &serde_json::Value, version: HashVersion) -> String {
+    let hash = compute_json_hash(input_data, version);
     format!("0x{}", hex::encode(hash))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_hash_is_key_order_independent() {
+        let a: serde_json::Value = serde_json::from_str(r#"{"text": "hi", "lang": "en"}"#).unwrap();
+        let b: serde_json::Value = serde_json::from_str(r#"{ "lang":"en","text":"hi" }"#).unwrap();
+        assert_eq!(compute_input_hash(&a), compute_input_hash(&b));
+    }
+
+    #[test]
+    fn test_legacy_hash_recomputable() {
+        let input = serde_json::json!({"pixels": [0.5, 1.0]});
+        let legacy = compute_input_hash_versioned(&input, HashVersion::Legacy);
+        let expected = format!("0x{}", hex::encode(compute_hash(br#"{"pixels":[0.5,1.0]}"#)));
+        assert_eq!(legacy, expected);
+
+        // JCS renders 1.0 as 1, so the two versions differ here
+        assert_ne!(legacy, compute_input_hash(&input));
+    }
+}
@@ -1,7 +1,9 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
-use serde::Serialize;
+use crate::common::{HashVersion, IntentMessage, KeyEpoch, ProcessedDataResponse, ProviderKind};
+use crate::models::ModelEntry;
+use serde::{Deserialize, Serialize};
 
 /// Health check response
 #[derive(Debug, Serialize)]
@@ -11,18 +13,53 @@ pub struct HealthResponse {
     pub version: String,
 }
 
+/// Attestation request, from the query string or a JSON body
+#[derive(Debug, Default, Deserialize)]
+pub struct AttestationParams {
+    /// Hex-encoded client challenge to embed in the document
+    #[serde(default)]
+    pub nonce: Option<String>,
+}
+
 /// Attestation response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AttestationResponse {
+    /// Platform that produced `attestation`
+    #[serde(default)]
+    pub provider: ProviderKind,
     pub attestation: String,
     pub timestamp: u64,
+    /// Hex-encoded nonce embedded in the document
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Timestamp signed into the document (milliseconds)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_timestamp: Option<u64>,
 }
 
 /// Public key response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PublicKeyResponse {
+    /// Current signing key
     pub public_key: String,
     pub format: String,
+    pub key_id: String,
+    /// Current and previous keys with their validity windows, oldest first
+    pub keys: Vec<KeyEpoch>,
+}
+
+/// Models listed in the manifest, as served by `/models`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelListResponse {
+    pub models: Vec<ModelEntry>,
+}
+
+/// Query of `/models/:id/schema`
+#[derive(Debug, Default, Deserialize)]
+pub struct ModelSchemaParams {
+    /// Expected hex SHA-256 of a model only registered on-chain
+    #[serde(default)]
+    pub model_sha256: Option<String>,
 }
 
 /// Error response
@@ -30,3 +67,31 @@ pub struct PublicKeyResponse {
 pub struct ErrorResponse {
     pub error: String,
 }
+
+/// Inference response to backend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InferenceResponse {
+    pub job_id: String,
+    pub model_id: String,
+    /// Signed as canonical JSON bytes, see `common::canonical::bcs_json`
+    #[serde(with = "crate::common::canonical::bcs_json")]
+    pub result: serde_json::Value,
+    pub input_hash: String,
+    pub computation_metadata: ComputationMetadata,
+}
+
+/// Computation metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComputationMetadata {
+    pub timestamp: u64,
+    pub model_version: String,
+    pub inference_time_ms: u64,
+    /// Encoding used to compute `input_hash`, required so it is never guessed
+    pub input_hash_version: HashVersion,
+    /// Hex-encoded SHA-256 of the model file, checked before it was loaded
+    #[serde(default)]
+    pub model_sha256: String,
+}
+
+/// Signed inference response as returned by `/process_data`
+pub type SignedInferenceResponse = ProcessedDataResponse<IntentMessage<InferenceResponse>>;
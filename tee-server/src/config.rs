@@ -0,0 +1,836 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Server configuration
+//!
+//! One [`Config`] is assembled from, in increasing precedence: built-in
+//! defaults, a TOML file (`--config` or `CONFIG_FILE`), environment
+//! variables and command-line flags. Every problem found along the way is
+//! collected, so a bad deployment reports all of its mistakes at once:
+//!
+//! ```toml
+//! [server]
+//! host = "0.0.0.0"                 # HOST, --host
+//! port = 3000                      # PORT, --port
+//! cors_origins = ["*"]             # CORS_ORIGINS, --cors-origin; [] disables CORS
+//! listener = "tcp"                 # LISTENER, --listener; "vsock" inside an enclave
+//! vsock_cid = 4294967295           # VSOCK_CID, --vsock-cid; default any CID
+//! vsock_port = 3000                # VSOCK_PORT, --vsock-port
+//!
+//! [limits]
+//! max_request_bytes = 10485760     # MAX_REQUEST_SIZE, --max-request-bytes
+//! request_timeout_secs = 30        # API_TIMEOUT_SECONDS, --request-timeout-secs; 0 = none
+//!
+//! [models]
+//! dir = "/app/models"              # MODELS_DIR, --models-dir
+//! cache_budget_bytes = 0           # MODEL_CACHE_BUDGET_BYTES; 0 = no limit
+//! backends = { mnist-classifier = "tract" }  # MODEL_BACKENDS
+//! registry_id = "0x..."            # MODEL_REGISTRY_ID; resolves unlisted models on-chain
+//! sui_rpc_url = "https://fullnode.testnet.sui.io:443"  # SUI_RPC_URL
+//! walrus_aggregators = ["https://aggregator.walrus-testnet.walrus.space"]  # WALRUS_AGGREGATORS
+//! walrus_cache_dir = "/app/data/walrus"  # WALRUS_CACHE_DIR
+//!
+//! [attestation]
+//! provider = "nitro"               # ATTESTATION_PROVIDER, --attestation-provider
+//! pcr_policy = "configs/pcr_policy.toml"  # PCR_POLICY_PATH, --pcr-policy
+//!
+//! [egress]
+//! vsock_port = 0                   # EGRESS_VSOCK_PORT, --egress-vsock-port; 0 = direct network
+//! vsock_cid = 3                    # EGRESS_VSOCK_CID; the parent instance
+//! proxy_addr = "127.0.0.1:3128"    # EGRESS_PROXY_ADDR; local end of the relay
+//!
+//! [keys]
+//! keystore_path = "/app/data/keystore.json"  # KEYSTORE_PATH, --keystore-path
+//! sealing_key = "..."              # KEYSTORE_SEALING_KEY
+//! rotation_interval_secs = 0       # KEY_ROTATION_INTERVAL_SECS; 0 = never
+//! intent_version = 0               # INTENT_VERSION
+//! intent_app_id = 0                # INTENT_APP_ID
+//! ```
+//!
+//! With `[egress]` enabled, Walrus and Sui registry requests go through
+//! [`egress`](crate::egress) to the parent instance.
+
+use crate::common::{parse_sealing_key, IntentConfig, ProviderKind};
+use crate::models::{BackendKind, DEFAULT_MODELS_DIR};
+use crate::server::ServerConfig;
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Effective server configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerSettings,
+    pub limits: LimitSettings,
+    pub models: ModelSettings,
+    pub attestation: AttestationSettings,
+    pub egress: EgressSettings,
+    pub keys: KeySettings,
+}
+
+/// Listening socket and CORS
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    /// IP address to bind
+    pub host: String,
+    pub port: u16,
+    /// Allowed origins, `*` for any; empty disables CORS
+    pub cors_origins: Vec<String>,
+    /// Socket family to serve on
+    pub listener: ListenerKind,
+    /// CID to bind with the vsock listener, `VMADDR_CID_ANY` by default
+    pub vsock_cid: u32,
+    pub vsock_port: u32,
+}
+
+/// Transport the server listens on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ListenerKind {
+    /// `host`:`port` over TCP
+    #[default]
+    Tcp,
+    /// `vsock_cid`:`vsock_port` over AF_VSOCK, for Nitro Enclaves (Linux only)
+    Vsock,
+}
+
+impl FromStr for ListenerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        match s.to_ascii_lowercase().as_str() {
+            "tcp" => Ok(ListenerKind::Tcp),
+            "vsock" => Ok(ListenerKind::Vsock),
+            other => Err(format!(
+                "unknown listener {:?} (expected tcp or vsock)",
+                other
+            )),
+        }
+    }
+}
+
+/// Request limits
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitSettings {
+    /// Largest accepted request body
+    pub max_request_bytes: usize,
+    /// Time a request may take before it is answered with 408; 0 for no limit
+    pub request_timeout_secs: u64,
+}
+
+/// Where models come from and how much memory they may use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModelSettings {
+    /// Directory with the model files and `models.toml`
+    pub dir: PathBuf,
+    /// Memory budget of the model cache; 0 for no limit
+    pub cache_budget_bytes: usize,
+    /// Per-model engine overrides
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub backends: BTreeMap<String, BackendKind>,
+    /// Object id of the on-chain `ModelRegistry`; unlisted models are
+    /// resolved through it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registry_id: Option<String>,
+    /// Sui fullnode the registry is read from
+    pub sui_rpc_url: String,
+    /// Walrus aggregators, tried in order; required with a registry
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub walrus_aggregators: Vec<String>,
+    /// Cache of downloaded Walrus blobs
+    pub walrus_cache_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttestationSettings {
+    pub provider: ProviderKind,
+    /// Allowed PCRs; required in release builds on platforms with PCRs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pcr_policy: Option<PathBuf>,
+}
+
+/// Outbound HTTP(S) from an enclave, relayed to the parent's egress proxy
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EgressSettings {
+    /// vsock port of the parent's proxy; 0 uses the network directly
+    pub vsock_port: u32,
+    /// CID of the parent instance
+    pub vsock_cid: u32,
+    /// Local address HTTP clients use as their proxy
+    pub proxy_addr: SocketAddr,
+}
+
+/// Signing key storage and intent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeySettings {
+    /// Sealed keystore file; without one the key is ephemeral
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keystore_path: Option<PathBuf>,
+    /// Hex-encoded 32-byte key sealing the keystore
+    #[serde(skip_serializing_if = "Option::is_none", serialize_with = "redacted")]
+    pub sealing_key: Option<String>,
+    /// Seconds between key rotations; 0 never rotates
+    pub rotation_interval_secs: u64,
+    pub intent_version: u8,
+    pub intent_app_id: u8,
+}
+
+impl KeySettings {
+    /// Rotation interval in milliseconds, `None` if keys are never rotated
+    /// or the interval is out of range (see [`Config::validate`])
+    pub fn rotation_interval_ms(&self) -> Option<u64> {
+        self.rotation_interval_secs
+            .checked_mul(1000)
+            .filter(|ms| *ms > 0)
+    }
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            cors_origins: vec!["*".to_string()],
+            listener: ListenerKind::Tcp,
+            // VMADDR_CID_ANY
+            vsock_cid: u32::MAX,
+            vsock_port: 3000,
+        }
+    }
+}
+
+impl Default for LimitSettings {
+    fn default() -> Self {
+        Self {
+            max_request_bytes: 10 * 1024 * 1024,
+            request_timeout_secs: 30,
+        }
+    }
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from(DEFAULT_MODELS_DIR),
+            cache_budget_bytes: 0,
+            backends: BTreeMap::new(),
+            registry_id: None,
+            sui_rpc_url: "https://fullnode.testnet.sui.io:443".to_string(),
+            walrus_aggregators: Vec::new(),
+            walrus_cache_dir: PathBuf::from("/app/data/walrus"),
+        }
+    }
+}
+
+impl Default for EgressSettings {
+    fn default() -> Self {
+        Self {
+            vsock_port: 0,
+            // NITRO_PARENT_CID
+            vsock_cid: 3,
+            proxy_addr: SocketAddr::from(([127, 0, 0, 1], 3128)),
+        }
+    }
+}
+
+impl Default for KeySettings {
+    fn default() -> Self {
+        let intent = IntentConfig::default();
+        Self {
+            keystore_path: None,
+            sealing_key: None,
+            rotation_interval_secs: 0,
+            intent_version: intent.version,
+            intent_app_id: intent.app_id,
+        }
+    }
+}
+
+/// Command-line overrides, the last configuration layer
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct ConfigArgs {
+    /// TOML configuration file (default: `CONFIG_FILE`)
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Address to bind
+    #[arg(long)]
+    pub host: Option<String>,
+
+    #[arg(long)]
+    pub port: Option<u16>,
+
+    /// Serve over TCP or, inside an enclave, vsock
+    #[arg(long, value_enum)]
+    pub listener: Option<ListenerKind>,
+
+    #[arg(long, value_name = "CID")]
+    pub vsock_cid: Option<u32>,
+
+    #[arg(long, value_name = "PORT")]
+    pub vsock_port: Option<u32>,
+
+    /// Allowed CORS origin, repeatable; `*` allows any
+    #[arg(long = "cors-origin", value_name = "ORIGIN")]
+    pub cors_origins: Vec<String>,
+
+    #[arg(long, value_name = "BYTES")]
+    pub max_request_bytes: Option<usize>,
+
+    #[arg(long, value_name = "SECS")]
+    pub request_timeout_secs: Option<u64>,
+
+    /// Directory with the model files and manifest
+    #[arg(long, value_name = "PATH")]
+    pub models_dir: Option<PathBuf>,
+
+    /// nitro, sev-snp, tdx or software
+    #[arg(long, value_name = "PROVIDER")]
+    pub attestation_provider: Option<String>,
+
+    #[arg(long, value_name = "PATH")]
+    pub pcr_policy: Option<PathBuf>,
+
+    /// Relay outbound requests to the parent's egress proxy on this vsock port
+    #[arg(long, value_name = "PORT")]
+    pub egress_vsock_port: Option<u32>,
+
+    /// Sealed keystore file
+    #[arg(long, value_name = "PATH")]
+    pub keystore_path: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load from the file, process environment and `args`
+    pub fn load(args: &ConfigArgs) -> Result<Self> {
+        Self::load_from(args, |name| std::env::var(name).ok())
+    }
+
+    /// Load with `env` standing in for the process environment
+    pub fn load_from(args: &ConfigArgs, env: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        // Empty variables count as unset, as in `.env.example`
+        let env = |name: &str| env(name).filter(|value| !value.trim().is_empty());
+        let mut errors = Vec::new();
+
+        let file = args
+            .config
+            .clone()
+            .or_else(|| env("CONFIG_FILE").map(PathBuf::from));
+        let mut config = match &file {
+            Some(path) => Self::from_file(path).unwrap_or_else(|e| {
+                errors.push(e);
+                Self::default()
+            }),
+            None => Self::default(),
+        };
+        config.apply_env(&env, &mut errors);
+        config.apply_args(args, &mut errors);
+        errors.extend(config.validate());
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(EnclaveError::ValidationError(format!(
+                "Invalid configuration:\n  - {}",
+                errors.join("\n  - ")
+            )))
+        }
+    }
+
+    /// Parse a TOML file on top of the defaults
+    fn from_file(path: &Path) -> std::result::Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        toml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    fn apply_env(&mut self, env: &impl Fn(&str) -> Option<String>, errors: &mut Vec<String>) {
+        let mut set =
+            |name: &str, apply: &mut dyn FnMut(&str) -> std::result::Result<(), String>| {
+                if let Some(value) = env(name) {
+                    if let Err(e) = apply(value.trim()) {
+                        errors.push(format!("{}: {}", name, e));
+                    }
+                }
+            };
+
+        set("HOST", &mut |v| assign(&mut self.server.host, v));
+        set("PORT", &mut |v| assign(&mut self.server.port, v));
+        set("CORS_ORIGINS", &mut |v| {
+            self.server.cors_origins = split_list(v);
+            Ok(())
+        });
+        set("LISTENER", &mut |v| assign(&mut self.server.listener, v));
+        set("VSOCK_CID", &mut |v| assign(&mut self.server.vsock_cid, v));
+        set("VSOCK_PORT", &mut |v| {
+            assign(&mut self.server.vsock_port, v)
+        });
+        set("MAX_REQUEST_SIZE", &mut |v| {
+            assign(&mut self.limits.max_request_bytes, v)
+        });
+        set("API_TIMEOUT_SECONDS", &mut |v| {
+            assign(&mut self.limits.request_timeout_secs, v)
+        });
+        set("MODELS_DIR", &mut |v| assign(&mut self.models.dir, v));
+        set("MODEL_CACHE_BUDGET_BYTES", &mut |v| {
+            assign(&mut self.models.cache_budget_bytes, v)
+        });
+        set("MODEL_BACKENDS", &mut |v| {
+            let assignments = BackendKind::parse_assignments(v).map_err(|e| e.to_string())?;
+            self.models.backends.extend(assignments);
+            Ok(())
+        });
+        set("MODEL_REGISTRY_ID", &mut |v| {
+            self.models.registry_id = Some(v.to_string());
+            Ok(())
+        });
+        set("SUI_RPC_URL", &mut |v| {
+            self.models.sui_rpc_url = v.to_string();
+            Ok(())
+        });
+        set("WALRUS_AGGREGATORS", &mut |v| {
+            self.models.walrus_aggregators = split_list(v);
+            Ok(())
+        });
+        set("WALRUS_CACHE_DIR", &mut |v| {
+            assign(&mut self.models.walrus_cache_dir, v)
+        });
+        set("ATTESTATION_PROVIDER", &mut |v| {
+            assign(&mut self.attestation.provider, v)
+        });
+        set("PCR_POLICY_PATH", &mut |v| {
+            self.attestation.pcr_policy = Some(PathBuf::from(v));
+            Ok(())
+        });
+        set("EGRESS_VSOCK_PORT", &mut |v| {
+            assign(&mut self.egress.vsock_port, v)
+        });
+        set("EGRESS_VSOCK_CID", &mut |v| {
+            assign(&mut self.egress.vsock_cid, v)
+        });
+        set("EGRESS_PROXY_ADDR", &mut |v| {
+            assign(&mut self.egress.proxy_addr, v)
+        });
+        set("KEYSTORE_PATH", &mut |v| {
+            self.keys.keystore_path = Some(PathBuf::from(v));
+            Ok(())
+        });
+        set("KEYSTORE_SEALING_KEY", &mut |v| {
+            self.keys.sealing_key = Some(v.to_string());
+            Ok(())
+        });
+        set("KEY_ROTATION_INTERVAL_SECS", &mut |v| {
+            assign(&mut self.keys.rotation_interval_secs, v)
+        });
+        set("INTENT_VERSION", &mut |v| {
+            assign(&mut self.keys.intent_version, v)
+        });
+        set("INTENT_APP_ID", &mut |v| {
+            assign(&mut self.keys.intent_app_id, v)
+        });
+    }
+
+    fn apply_args(&mut self, args: &ConfigArgs, errors: &mut Vec<String>) {
+        if let Some(host) = &args.host {
+            self.server.host = host.clone();
+        }
+        if let Some(port) = args.port {
+            self.server.port = port;
+        }
+        if let Some(listener) = args.listener {
+            self.server.listener = listener;
+        }
+        if let Some(cid) = args.vsock_cid {
+            self.server.vsock_cid = cid;
+        }
+        if let Some(port) = args.vsock_port {
+            self.server.vsock_port = port;
+        }
+        if !args.cors_origins.is_empty() {
+            self.server.cors_origins = args.cors_origins.clone();
+        }
+        if let Some(bytes) = args.max_request_bytes {
+            self.limits.max_request_bytes = bytes;
+        }
+        if let Some(secs) = args.request_timeout_secs {
+            self.limits.request_timeout_secs = secs;
+        }
+        if let Some(dir) = &args.models_dir {
+            self.models.dir = dir.clone();
+        }
+        if let Some(provider) = &args.attestation_provider {
+            if let Err(e) = assign(&mut self.attestation.provider, provider) {
+                errors.push(format!("--attestation-provider: {}", e));
+            }
+        }
+        if let Some(path) = &args.pcr_policy {
+            self.attestation.pcr_policy = Some(path.clone());
+        }
+        if let Some(port) = args.egress_vsock_port {
+            self.egress.vsock_port = port;
+        }
+        if let Some(path) = &args.keystore_path {
+            self.keys.keystore_path = Some(path.clone());
+        }
+    }
+
+    /// Every problem with the values, empty if the configuration is usable
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.server.host.parse::<IpAddr>().is_err() {
+            errors.push(format!(
+                "server.host: {:?} is not an IP address",
+                self.server.host
+            ));
+        }
+        if self.server.listener == ListenerKind::Vsock && !cfg!(target_os = "linux") {
+            errors.push("server.listener: vsock is only supported on Linux".into());
+        }
+        let any_origin = self.server.cors_origins.iter().any(|origin| origin == "*");
+        if any_origin && self.server.cors_origins.len() > 1 {
+            errors.push("server.cors_origins: \"*\" cannot be combined with other origins".into());
+        }
+        for origin in self.server.cors_origins.iter().filter(|o| *o != "*") {
+            let valid = (origin.starts_with("http://") || origin.starts_with("https://"))
+                && !origin.ends_with('/')
+                && axum::http::HeaderValue::from_str(origin).is_ok();
+            if !valid {
+                errors.push(format!(
+                    "server.cors_origins: {:?} is not an origin like https://app.example.com",
+                    origin
+                ));
+            }
+        }
+
+        if self.limits.max_request_bytes == 0 {
+            errors.push("limits.max_request_bytes: must be greater than 0".into());
+        }
+
+        if !self.models.dir.is_dir() {
+            errors.push(format!(
+                "models.dir: {} is not a directory",
+                self.models.dir.display()
+            ));
+        }
+        for (model_id, backend) in &self.models.backends {
+            if !backend.is_compiled() {
+                errors.push(format!(
+                    "models.backends: {} needs the {} feature, which this build lacks",
+                    model_id,
+                    backend.feature()
+                ));
+            }
+        }
+
+        if let Some(registry_id) = &self.models.registry_id {
+            let valid = registry_id
+                .strip_prefix("0x")
+                .is_some_and(|hex| !hex.is_empty() && hex.bytes().all(|b| b.is_ascii_hexdigit()));
+            if !valid {
+                errors.push(format!(
+                    "models.registry_id: {:?} is not a Sui object id like 0x5f3a...",
+                    registry_id
+                ));
+            }
+            if !is_http_url(&self.models.sui_rpc_url) {
+                errors.push(format!(
+                    "models.sui_rpc_url: {:?} is not an http(s) URL",
+                    self.models.sui_rpc_url
+                ));
+            }
+            if self.models.walrus_aggregators.is_empty() {
+                errors.push(
+                    "models.walrus_aggregators: required when models.registry_id is set".into(),
+                );
+            }
+        }
+        for aggregator in &self.models.walrus_aggregators {
+            if !is_http_url(aggregator) {
+                errors.push(format!(
+                    "models.walrus_aggregators: {:?} is not an http(s) URL",
+                    aggregator
+                ));
+            }
+        }
+
+        if let Some(path) = &self.attestation.pcr_policy {
+            if !path.is_file() {
+                errors.push(format!(
+                    "attestation.pcr_policy: {} does not exist",
+                    path.display()
+                ));
+            }
+        }
+
+        if self.egress.vsock_port != 0 && !cfg!(target_os = "linux") {
+            errors.push("egress.vsock_port: vsock is only supported on Linux".into());
+        }
+
+        if self.keys.rotation_interval_secs.checked_mul(1000).is_none() {
+            errors.push(format!(
+                "keys.rotation_interval_secs: {} is out of range",
+                self.keys.rotation_interval_secs
+            ));
+        }
+        match (&self.keys.keystore_path, &self.keys.sealing_key) {
+            (Some(_), None) => {
+                errors.push("keys.sealing_key: required when keys.keystore_path is set".into())
+            }
+            (None, Some(_)) => {
+                errors.push("keys.sealing_key: set without keys.keystore_path".into())
+            }
+            (_, Some(key)) => {
+                if let Err(e) = parse_sealing_key(key) {
+                    errors.push(format!("keys.sealing_key: {}", e));
+                }
+            }
+            (None, None) => {}
+        }
+
+        errors
+    }
+
+    /// Address to bind; only valid after [`Config::validate`]
+    pub fn bind_addr(&self) -> SocketAddr {
+        let host = self.server.host.parse().unwrap_or([0, 0, 0, 0].into());
+        SocketAddr::new(host, self.server.port)
+    }
+
+    /// Router settings
+    pub fn server_config(&self) -> ServerConfig {
+        ServerConfig {
+            intent: IntentConfig {
+                version: self.keys.intent_version,
+                app_id: self.keys.intent_app_id,
+            },
+            cors_origins: self.server.cors_origins.clone(),
+            max_request_bytes: self.limits.max_request_bytes,
+            request_timeout: (self.limits.request_timeout_secs > 0)
+                .then(|| Duration::from_secs(self.limits.request_timeout_secs)),
+        }
+    }
+
+    /// Proxy URL for outbound HTTP clients, if egress goes through the parent
+    pub fn egress_proxy_url(&self) -> Option<String> {
+        (self.egress.vsock_port != 0).then(|| format!("http://{}", self.egress.proxy_addr))
+    }
+
+    /// TOML for `--print-config`, with the sealing key redacted
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("configuration serializes to TOML")
+    }
+}
+
+/// Parse `value` into `field`
+fn assign<T>(field: &mut T, value: &str) -> std::result::Result<(), String>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    *field = value
+        .parse()
+        .map_err(|e: T::Err| format!("invalid value {:?}: {}", value, e))?;
+    Ok(())
+}
+
+fn is_http_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn redacted<S: Serializer>(
+    value: &Option<String>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    match value {
+        Some(_) => serializer.serialize_str("<redacted>"),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("config-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = temp_dir("layers");
+        let file = dir.join("server.toml");
+        std::fs::write(
+            &file,
+            format!(
+                "[server]\nhost = \"127.0.0.1\"\nport = 4000\ncors_origins = []\n\
+                 [models]\ndir = {:?}\n[limits]\nrequest_timeout_secs = 0\n",
+                dir
+            ),
+        )
+        .unwrap();
+
+        let args = ConfigArgs {
+            config: Some(file),
+            port: Some(5000),
+            vsock_cid: Some(16),
+            ..ConfigArgs::default()
+        };
+        let config = Config::load_from(
+            &args,
+            env(&[
+                ("PORT", "4500"),
+                ("CORS_ORIGINS", "https://a.example, https://b.example"),
+                ("ATTESTATION_PROVIDER", "software"),
+                ("MODEL_BACKENDS", ""),
+                ("VSOCK_PORT", "5005"),
+                ("EGRESS_PROXY_ADDR", "127.0.0.1:8080"),
+                ("MODEL_REGISTRY_ID", "0x5f3a"),
+                ("WALRUS_AGGREGATORS", "https://a.walrus, https://b.walrus"),
+            ]),
+        )
+        .unwrap();
+
+        assert_eq!(config.bind_addr(), "127.0.0.1:5000".parse().unwrap());
+        assert_eq!(
+            config.server.cors_origins,
+            ["https://a.example", "https://b.example"]
+        );
+        assert_eq!(config.models.dir, dir);
+        assert_eq!(config.models.registry_id.as_deref(), Some("0x5f3a"));
+        assert_eq!(
+            config.models.walrus_aggregators,
+            ["https://a.walrus", "https://b.walrus"]
+        );
+        assert_eq!(config.server.listener, ListenerKind::Tcp);
+        assert_eq!(
+            (config.server.vsock_cid, config.server.vsock_port),
+            (16, 5005)
+        );
+        assert_eq!(config.attestation.provider, ProviderKind::Software);
+        assert_eq!(config.egress_proxy_url(), None);
+        let egress = Config {
+            egress: EgressSettings {
+                vsock_port: 8001,
+                ..config.egress.clone()
+            },
+            ..config.clone()
+        };
+        assert_eq!(
+            egress.egress_proxy_url().as_deref(),
+            Some("http://127.0.0.1:8080")
+        );
+        assert_eq!(
+            config.limits,
+            LimitSettings {
+                request_timeout_secs: 0,
+                ..LimitSettings::default()
+            }
+        );
+        assert_eq!(config.server_config().request_timeout, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_all_errors_are_reported() {
+        let args = ConfigArgs {
+            config: Some(PathBuf::from("/nonexistent/server.toml")),
+            attestation_provider: Some("sgx".to_string()),
+            ..ConfigArgs::default()
+        };
+        let err = Config::load_from(
+            &args,
+            env(&[
+                ("HOST", "example.com"),
+                ("PORT", "http"),
+                ("LISTENER", "udp"),
+                ("CORS_ORIGINS", "*,https://a.example/"),
+                ("MODELS_DIR", "/nonexistent/models"),
+                ("KEYSTORE_PATH", "/app/data/keystore.json"),
+                ("EGRESS_PROXY_ADDR", "localhost"),
+                ("KEY_ROTATION_INTERVAL_SECS", "18446744073709552"),
+                ("MODEL_REGISTRY_ID", "registry"),
+            ]),
+        )
+        .unwrap_err()
+        .to_string();
+
+        for expected in [
+            "/nonexistent/server.toml",
+            "PORT",
+            "LISTENER",
+            "--attestation-provider",
+            "server.host",
+            "\"*\" cannot be combined",
+            "https://a.example/",
+            "models.dir",
+            "keys.sealing_key",
+            "EGRESS_PROXY_ADDR",
+            "keys.rotation_interval_secs",
+            "models.registry_id",
+            "models.walrus_aggregators: required",
+        ] {
+            assert!(
+                err.contains(expected),
+                "{} missing from:\n{}",
+                expected,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_print_config_redacts_the_sealing_key() {
+        let config = Config {
+            keys: KeySettings {
+                keystore_path: Some(PathBuf::from("/app/data/keystore.json")),
+                sealing_key: Some("11".repeat(32)),
+                ..KeySettings::default()
+            },
+            ..Config::default()
+        };
+        let printed = config.to_toml();
+        assert!(printed.contains("<redacted>"));
+        assert!(!printed.contains(&"11".repeat(32)));
+
+        let reparsed: Config = toml::from_str(&printed).unwrap();
+        assert_eq!(reparsed.server, config.server);
+        assert!(toml::from_str::<Config>("[server]\nhots = \"x\"").is_err());
+
+        let example: Config = toml::from_str(include_str!("../configs/server.toml")).unwrap();
+        assert_eq!(example.server, ServerSettings::default());
+        assert_eq!(example.egress, EgressSettings::default());
+    }
+}
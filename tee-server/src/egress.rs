@@ -0,0 +1,643 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Outbound HTTP(S) for an enclave without a network
+//!
+//! Inside the enclave, [`spawn_relay`] listens on a loopback address and
+//! relays every connection over vsock to the parent instance. HTTP clients
+//! such as the Walrus and Sui clients use that address as their proxy, so
+//! `https` requests arrive as `CONNECT host:port` tunnels and plain `http`
+//! ones as absolute-form requests.
+//!
+//! On the parent, [`serve`] (the `synapse-egress-proxy` binary) answers
+//! those proxy requests, opening a connection only to destinations on its
+//! [`Allowlist`]. TLS runs end to end between the enclave and the
+//! destination; the parent only sees host names and ciphertext.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info, warn};
+
+/// Largest request head the proxy reads before giving up
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// Time allowed for reaching a destination
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Destinations the proxy may connect to
+///
+/// Entries are `host`, `host:port`, `*.domain` or `*.domain:port`; IPv6
+/// addresses go in brackets. Without a port any port is allowed, and
+/// `*.domain` matches subdomains of `domain` but not `domain` itself.
+/// Matching is on the name the client asked for, before DNS resolution.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Allowlist {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    host: String,
+    wildcard: bool,
+    port: Option<u16>,
+}
+
+impl Allowlist {
+    pub fn new<I, S>(entries: I) -> std::result::Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let rules = entries
+            .into_iter()
+            .map(|entry| entry.as_ref().parse())
+            .collect::<std::result::Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn allows(&self, host: &str, port: u16) -> bool {
+        let host = normalize_host(host);
+        self.rules.iter().any(|rule| {
+            let host_matches = if rule.wildcard {
+                host.strip_suffix(rule.host.as_str())
+                    .is_some_and(|prefix| prefix.len() > 1 && prefix.ends_with('.'))
+            } else {
+                host == rule.host
+            };
+            host_matches && rule.port.is_none_or(|allowed| allowed == port)
+        })
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(entry: &str) -> std::result::Result<Self, String> {
+        let invalid = || format!("invalid allowlist entry {:?}", entry);
+        let (host, port) = split_host_port(entry.trim()).ok_or_else(invalid)?;
+        let (host, wildcard) = match host.strip_prefix("*.") {
+            Some(domain) => (domain, true),
+            None => (host, false),
+        };
+        if host.is_empty() || host.contains('*') {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: normalize_host(host),
+            wildcard,
+            port,
+        })
+    }
+}
+
+impl fmt::Display for Allowlist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            if rule.wildcard {
+                f.write_str("*.")?;
+            }
+            if rule.host.contains(':') {
+                write!(f, "[{}]", rule.host)?;
+            } else {
+                f.write_str(&rule.host)?;
+            }
+            if let Some(port) = rule.port {
+                write!(f, ":{}", port)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Split `host[:port]` or `[v6][:port]`; `None` if malformed
+fn split_host_port(authority: &str) -> Option<(&str, Option<u16>)> {
+    let (host, port) = match authority.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest.split_once(']')?;
+            match rest {
+                "" => (host, None),
+                _ => (host, Some(rest.strip_prefix(':')?)),
+            }
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => Some(port.parse().ok().filter(|&port| port != 0)?),
+        None => None,
+    };
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._:*".contains(c));
+    valid.then_some((host, port))
+}
+
+/// What a client asked the proxy for
+#[derive(Debug, PartialEq, Eq)]
+enum ProxyRequest {
+    /// `CONNECT host:port`, answered with a raw tunnel
+    Connect { host: String, port: u16 },
+    /// `GET http://host/path`, forwarded as a single request
+    Forward {
+        host: String,
+        port: u16,
+        /// Head rewritten for the destination
+        head: Vec<u8>,
+    },
+}
+
+/// Refusal sent back to the client
+#[derive(Debug, PartialEq, Eq)]
+struct Rejection {
+    status: &'static str,
+    reason: String,
+}
+
+impl Rejection {
+    fn new(status: &'static str, reason: impl Into<String>) -> Self {
+        Self {
+            status,
+            reason: reason.into(),
+        }
+    }
+
+    async fn send<S: AsyncWrite + Unpin>(&self, client: &mut S) -> io::Result<()> {
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.reason.len(),
+            self.reason
+        );
+        client.write_all(response.as_bytes()).await?;
+        client.shutdown().await
+    }
+}
+
+/// Parse a request head, everything before the blank line
+fn parse_request(head: &[u8]) -> std::result::Result<ProxyRequest, Rejection> {
+    let bad_request = |reason: &str| Rejection::new("400 Bad Request", reason);
+    let head = std::str::from_utf8(head).map_err(|_| bad_request("request head is not UTF-8"))?;
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().unwrap_or_default();
+    let [method, target, version] = request_line.split(' ').collect::<Vec<_>>()[..] else {
+        return Err(bad_request("malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(bad_request("only HTTP/1.x is supported"));
+    }
+
+    if method == "CONNECT" {
+        return match split_host_port(target) {
+            Some((host, Some(port))) => Ok(ProxyRequest::Connect {
+                host: host.to_string(),
+                port,
+            }),
+            _ => Err(bad_request("CONNECT needs host:port")),
+        };
+    }
+
+    let Some(rest) = target.strip_prefix("http://") else {
+        return Err(bad_request(
+            "expected CONNECT or an absolute http:// URL; use CONNECT for https",
+        ));
+    };
+    let path_start = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(path_start);
+    let Some((host, port)) = split_host_port(authority) else {
+        return Err(bad_request("malformed host in URL"));
+    };
+
+    // One request per connection: the destination closes after answering,
+    // so a client cannot reuse the connection for a host we did not check
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{}", path)
+    };
+    let mut rewritten = format!("{} {} {}\r\n", method, path, version);
+    let mut has_host = false;
+    for line in lines.filter(|line| !line.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        if [
+            "connection",
+            "proxy-connection",
+            "proxy-authorization",
+            "keep-alive",
+        ]
+        .iter()
+        .any(|hop| name.eq_ignore_ascii_case(hop))
+        {
+            continue;
+        }
+        has_host |= name.eq_ignore_ascii_case("host");
+        rewritten.push_str(line);
+        rewritten.push_str("\r\n");
+    }
+    if !has_host {
+        rewritten.push_str(&format!("Host: {}\r\n", authority));
+    }
+    rewritten.push_str("Connection: close\r\n\r\n");
+
+    Ok(ProxyRequest::Forward {
+        host: host.to_string(),
+        port: port.unwrap_or(80),
+        head: rewritten.into_bytes(),
+    })
+}
+
+/// Read up to the end of the request head; returns the head and any bytes
+/// the client already sent after it
+async fn read_head<S: AsyncRead + Unpin>(client: &mut S) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut buf = Vec::with_capacity(1024);
+    let mut chunk = [0u8; 1024];
+    loop {
+        let end = buf.windows(4).position(|window| window == b"\r\n\r\n");
+        if end.unwrap_or(buf.len()) > MAX_HEAD_BYTES {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "request head too large",
+            ));
+        }
+        if let Some(end) = end {
+            let rest = buf.split_off(end + 4);
+            buf.truncate(end);
+            return Ok((buf, rest));
+        }
+        let read = client.read(&mut chunk).await?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        buf.extend_from_slice(&chunk[..read]);
+    }
+}
+
+/// Answer one proxy client, tunnelling to its destination if allowed
+///
+/// Generic over the stream so the same code serves vsock on the parent and
+/// Unix sockets in tests.
+pub async fn handle<S>(mut client: S, allowlist: &Allowlist) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (head, early_data) = match read_head(&mut client).await {
+        Ok(read) => read,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            return Rejection::new("431 Request Header Fields Too Large", e.to_string())
+                .send(&mut client)
+                .await;
+        }
+        Err(e) => return Err(e),
+    };
+    let request = match parse_request(&head) {
+        Ok(request) => request,
+        Err(rejection) => return rejection.send(&mut client).await,
+    };
+    let (host, port) = match &request {
+        ProxyRequest::Connect { host, port } | ProxyRequest::Forward { host, port, .. } => {
+            (host.as_str(), *port)
+        }
+    };
+
+    if !allowlist.allows(host, port) {
+        warn!("Egress to {}:{} denied by the allowlist", host, port);
+        return Rejection::new("403 Forbidden", format!("{}:{} is not allowed", host, port))
+            .send(&mut client)
+            .await;
+    }
+
+    let upstream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect((host, port))).await;
+    let mut upstream = match upstream {
+        Ok(Ok(upstream)) => upstream,
+        Ok(Err(e)) => {
+            warn!("Egress to {}:{} failed: {}", host, port, e);
+            return Rejection::new("502 Bad Gateway", format!("{}:{}: {}", host, port, e))
+                .send(&mut client)
+                .await;
+        }
+        Err(_) => {
+            warn!("Egress to {}:{} timed out", host, port);
+            return Rejection::new("504 Gateway Timeout", format!("{}:{}", host, port))
+                .send(&mut client)
+                .await;
+        }
+    };
+
+    match &request {
+        ProxyRequest::Connect { .. } => {
+            info!("Tunnelling to {}:{}", host, port);
+            client
+                .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+                .await?;
+        }
+        ProxyRequest::Forward { head, .. } => {
+            info!("Forwarding request to {}:{}", host, port);
+            upstream.write_all(head).await?;
+        }
+    }
+    upstream.write_all(&early_data).await?;
+
+    let (sent, received) = tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    debug!(
+        "{}:{} closed after {} bytes out, {} bytes back",
+        host, port, sent, received
+    );
+    Ok(())
+}
+
+/// Parent side: run the proxy on every connection accepted by `listener`
+#[cfg(target_os = "linux")]
+pub async fn serve(
+    listener: crate::vsock::VsockListener,
+    allowlist: std::sync::Arc<Allowlist>,
+) -> io::Result<()> {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("vsock accept failed: {}", e);
+                continue;
+            }
+        };
+        let allowlist = allowlist.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &allowlist).await {
+                debug!("Egress client cid {} closed: {}", peer.cid(), e);
+            }
+        });
+    }
+}
+
+/// Enclave side: relay proxy clients on `addr` to the parent's egress
+/// proxy at `cid`:`port`, returning the proxy URL for HTTP clients
+#[cfg(target_os = "linux")]
+pub async fn spawn_relay(addr: std::net::SocketAddr, cid: u32, port: u32) -> io::Result<String> {
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    let url = format!("http://{}", listener.local_addr()?);
+    tokio::spawn(async move {
+        if let Err(e) = crate::vsock::forward_tcp(listener, cid, port).await {
+            warn!("Egress relay stopped: {}", e);
+        }
+    });
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::sui_registry::tests::{registered, spawn_rpc, REGISTRY_ID};
+    use crate::models::walrus::tests::{blob, spawn_aggregator, BLOB_ID};
+    use crate::models::{SuiRegistryClient, SuiRegistryConfig, WalrusClient, WalrusConfig};
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, UnixListener, UnixStream};
+
+    /// Parent proxy on a Unix socket, standing in for its vsock listener
+    fn spawn_proxy(name: &str, allowlist: Allowlist) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("egress-{}-{}.sock", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let allowlist = Arc::new(allowlist);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let allowlist = allowlist.clone();
+                tokio::spawn(async move { handle(stream, &allowlist).await });
+            }
+        });
+        path
+    }
+
+    /// Enclave relay from a TCP proxy port to `proxy`, as `spawn_relay`
+    /// does over vsock; returns the proxy URL
+    async fn spawn_relay_to(proxy: PathBuf) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut tcp, _)) = listener.accept().await {
+                let mut unix = UnixStream::connect(&proxy).await.unwrap();
+                tokio::spawn(async move {
+                    let _ = tokio::io::copy_bidirectional(&mut tcp, &mut unix).await;
+                });
+            }
+        });
+        url
+    }
+
+    async fn spawn_upstream() -> u16 {
+        let app = axum::Router::new().route("/hello", axum::routing::get(|| async { "hello" }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        port
+    }
+
+    async fn request(proxy: &PathBuf, head: &str) -> String {
+        let mut stream = UnixStream::connect(proxy).await.unwrap();
+        stream.write_all(head.as_bytes()).await.unwrap();
+        // The proxy may hang up on a request it did not read in full, so
+        // keep whatever arrived before the reset
+        let mut response = Vec::new();
+        let _ = stream.read_to_end(&mut response).await;
+        String::from_utf8(response).unwrap()
+    }
+
+    #[test]
+    fn test_allowlist() {
+        let allowlist = Allowlist::new([
+            "aggregator.walrus-testnet.walrus.space",
+            "*.sui.io:443",
+            "[::1]:8080",
+            "127.0.0.1",
+        ])
+        .unwrap();
+
+        assert!(allowlist.allows("aggregator.walrus-testnet.walrus.space", 443));
+        assert!(allowlist.allows("Aggregator.Walrus-Testnet.Walrus.Space.", 80));
+        assert!(!allowlist.allows("evil.walrus-testnet.walrus.space", 443));
+        assert!(allowlist.allows("fullnode.testnet.sui.io", 443));
+        assert!(!allowlist.allows("fullnode.testnet.sui.io", 80));
+        assert!(!allowlist.allows("sui.io", 443));
+        assert!(!allowlist.allows("notsui.io", 443));
+        assert!(allowlist.allows("::1", 8080));
+        assert!(allowlist.allows("127.0.0.1", 1));
+        assert!(!Allowlist::default().allows("127.0.0.1", 80));
+        assert_eq!(
+            allowlist.to_string(),
+            "aggregator.walrus-testnet.walrus.space, *.sui.io:443, [::1]:8080, 127.0.0.1"
+        );
+
+        for invalid in ["", "*", "*.", "host:0", "host:https", "a.*.com", "[::1"] {
+            assert!(Allowlist::new([invalid]).is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(
+            parse_request(b"CONNECT fullnode.testnet.sui.io:443 HTTP/1.1\r\nHost: x"),
+            Ok(ProxyRequest::Connect {
+                host: "fullnode.testnet.sui.io".to_string(),
+                port: 443
+            })
+        );
+
+        let ProxyRequest::Forward { host, port, head } = parse_request(
+            b"GET http://example.com/v1/blobs/x?y=1 HTTP/1.1\r\nhost: example.com\r\n\
+              Proxy-Connection: keep-alive\r\nAccept: */*",
+        )
+        .unwrap() else {
+            panic!("expected a forwarded request");
+        };
+        assert_eq!((host.as_str(), port), ("example.com", 80));
+        assert_eq!(
+            String::from_utf8(head).unwrap(),
+            "GET /v1/blobs/x?y=1 HTTP/1.1\r\nhost: example.com\r\nAccept: */*\r\n\
+             Connection: close\r\n\r\n"
+        );
+
+        for malformed in [
+            &b"CONNECT example.com HTTP/1.1"[..],
+            b"GET /relative HTTP/1.1",
+            b"GET https://example.com/ HTTP/1.1",
+            b"GET http://user@example.com/ HTTP/1.1",
+            b"GET http://example.com/ HTTP/2",
+            b"garbage",
+        ] {
+            assert!(parse_request(malformed).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connect_tunnel() {
+        let port = spawn_upstream().await;
+        let proxy = spawn_proxy("connect", Allowlist::new(["127.0.0.1"]).unwrap());
+
+        let mut stream = UnixStream::connect(&proxy).await.unwrap();
+        stream
+            .write_all(format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\n\r\n", port).as_bytes())
+            .await
+            .unwrap();
+        let (head, _) = read_head(&mut stream).await.unwrap();
+        assert_eq!(head, b"HTTP/1.1 200 Connection Established");
+        stream
+            .write_all(b"GET /hello HTTP/1.1\r\nHost: upstream\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("hello"));
+
+        let denied = request(&proxy, "CONNECT example.com:443 HTTP/1.1\r\n\r\n").await;
+        assert!(denied.starts_with("HTTP/1.1 403 Forbidden"), "{}", denied);
+        assert!(denied.ends_with("example.com:443 is not allowed"));
+
+        // Allowed, but nothing listens there
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_port = dead.local_addr().unwrap().port();
+        drop(dead);
+        let refused = request(
+            &proxy,
+            &format!("CONNECT 127.0.0.1:{} HTTP/1.1\r\n\r\n", dead_port),
+        )
+        .await;
+        assert!(
+            refused.starts_with("HTTP/1.1 502 Bad Gateway"),
+            "{}",
+            refused
+        );
+
+        let oversized = format!(
+            "GET http://127.0.0.1/ HTTP/1.1\r\nX: {}\r\n\r\n",
+            "a".repeat(MAX_HEAD_BYTES)
+        );
+        let oversized = request(&proxy, &oversized).await;
+        assert!(oversized.starts_with("HTTP/1.1 431"), "{}", oversized);
+    }
+
+    #[tokio::test]
+    async fn test_walrus_and_sui_through_proxy() {
+        let proxy_url = spawn_relay_to(spawn_proxy(
+            "clients",
+            Allowlist::new(["127.0.0.1"]).unwrap(),
+        ))
+        .await;
+
+        let models = vec![registered(BLOB_ID, "digits")];
+        let registry = SuiRegistryClient::new(SuiRegistryConfig {
+            proxy: Some(proxy_url.clone()),
+            ..SuiRegistryConfig::new(spawn_rpc(models.clone()).await, REGISTRY_ID)
+        })
+        .unwrap();
+        assert_eq!(registry.list_models().await.unwrap(), models);
+
+        let (aggregator, _) = spawn_aggregator(0).await;
+        let cache_dir = std::env::temp_dir().join(format!("egress-walrus-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        let walrus = WalrusClient::new(WalrusConfig {
+            proxy: Some(proxy_url),
+            ..WalrusConfig::new(vec![aggregator], &cache_dir)
+        })
+        .unwrap();
+        let fetched = walrus.fetch(BLOB_ID, None).await.unwrap();
+        assert_eq!(std::fs::read(&fetched.path).unwrap(), blob());
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+
+        // The same traffic through a parent that allows nothing local
+        let denied = spawn_relay_to(spawn_proxy(
+            "clients-denied",
+            Allowlist::new(["*.sui.io"]).unwrap(),
+        ))
+        .await;
+        let registry = SuiRegistryClient::new(SuiRegistryConfig {
+            proxy: Some(denied),
+            ..registry.config().clone()
+        })
+        .unwrap();
+        assert!(registry.list_models().await.is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the vsock_loopback kernel module"]
+    async fn test_relay_over_loopback_vsock() {
+        use crate::vsock::{tests::loopback_listener, VMADDR_CID_LOCAL};
+
+        let (listener, port) = loopback_listener();
+        tokio::spawn(serve(
+            listener,
+            Arc::new(Allowlist::new(["127.0.0.1"]).unwrap()),
+        ));
+        let proxy = spawn_relay("127.0.0.1:0".parse().unwrap(), VMADDR_CID_LOCAL, port)
+            .await
+            .unwrap();
+
+        let upstream = spawn_upstream().await;
+        let client = reqwest::Client::builder()
+            .proxy(reqwest::Proxy::all(&proxy).unwrap())
+            .build()
+            .unwrap();
+        let body = client
+            .get(format!("http://127.0.0.1:{}/hello", upstream))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "hello");
+    }
+}
@@ -1,20 +1,101 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
-// Temporarily comment out unimplemented modules
-// pub mod apps;
+pub mod apps;
 pub mod common;
-// pub mod models;
+pub mod config;
+pub mod egress;
+pub mod models;
+pub mod server;
+pub mod verify;
+#[cfg(target_os = "linux")]
+pub mod vsock;
+
+pub use config::Config;
+pub use server::{ServerBuilder, ServerConfig};
+
+use std::sync::{Arc, RwLock};
 
 /// Application state shared across handlers
 pub struct AppState {
-    /// Ephemeral keypair for signing responses
-    pub eph_kp: fastcrypto::ed25519::Ed25519KeyPair,
+    /// Signing keys and their rotation epochs
+    pub keystore: RwLock<common::Keystore>,
+    /// Intent version and app id embedded in signed responses
+    pub intent_config: common::IntentConfig,
+    /// Attestation source for the platform we run on
+    pub attestation: Arc<dyn common::AttestationProvider>,
+    /// Loads models and records their hashes
+    pub model_loader: models::ModelLoader,
 }
 
 impl AppState {
+    /// State with an ephemeral, never-rotated signing key
     pub fn new(eph_kp: fastcrypto::ed25519::Ed25519KeyPair) -> Self {
-        Self { eph_kp }
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Self::with_keystore(common::Keystore::ephemeral(eph_kp, now_ms))
+    }
+
+    /// State signing with `keystore` under the default intent, with Nitro
+    /// attestation and no models; reads nothing from the environment
+    pub fn with_keystore(keystore: common::Keystore) -> Self {
+        Self {
+            keystore: RwLock::new(keystore),
+            intent_config: common::IntentConfig::default(),
+            attestation: Arc::new(common::NitroNsm::new()),
+            model_loader: models::ModelLoader::with_models_dir(models::DEFAULT_MODELS_DIR),
+        }
+    }
+
+    /// Replace the attestation source, e.g. with `common::MockNsm`
+    pub fn with_attestation_provider(
+        mut self,
+        provider: Arc<dyn common::AttestationProvider>,
+    ) -> Self {
+        self.attestation = provider;
+        self
+    }
+
+    pub fn with_model_loader(mut self, model_loader: models::ModelLoader) -> Self {
+        self.model_loader = model_loader;
+        self
+    }
+
+    /// Attestation `user_data`: current signing key and loaded-models root
+    pub fn attestation_user_data(&self) -> Result<Vec<u8>> {
+        let signing_key = {
+            let keystore = self.keystore.read().unwrap();
+            hex::decode(&keystore.current_epoch().public_key).unwrap_or_default()
+        };
+        let models_root = self.model_loader.measurements()?.root()?;
+        Ok(common::attestation_user_data(&signing_key, &models_root))
+    }
+
+    /// Rotate the signing key if its epoch has expired
+    pub fn rotate_keys_if_due(&self, now_ms: u64) -> Result<()> {
+        if self.keystore.read().unwrap().rotation_due(now_ms) {
+            self.keystore.write().unwrap().rotate_if_due(now_ms)?;
+        }
+        Ok(())
+    }
+
+    /// Sign data with the current key under the configured intent
+    pub fn sign_response<T: serde::Serialize>(
+        &self,
+        data: T,
+        timestamp_ms: u64,
+        scope: common::IntentScope,
+    ) -> Result<common::ProcessedDataResponse<common::IntentMessage<T>>> {
+        self.rotate_keys_if_due(timestamp_ms)?;
+        let keystore = self.keystore.read().unwrap();
+        Ok(common::to_signed_response_with_intent(
+            keystore.current(),
+            data,
+            timestamp_ms,
+            self.intent_config.intent(scope),
+        ))
     }
 }
 
@@ -38,6 +119,12 @@ pub enum EnclaveError {
 
     #[error("Cryptography error: {0}")]
     CryptoError(String),
+
+    #[error("Attestation error: {0}")]
+    AttestationError(String),
+
+    #[error("Integrity error: {0}")]
+    IntegrityError(String),
 }
 
 // Implement conversion from EnclaveError to HTTP response
@@ -1,30 +1,46 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
-use axum::{
-    extract::State,
-    http::{header, Method, StatusCode},
-    response::IntoResponse,
-    routing::{get, post},
-    Json, Router,
-};
-use fastcrypto::ed25519::Ed25519KeyPair;
-use fastcrypto::traits::{KeyPair, ToFromBytes};
-use std::net::SocketAddr;
+use clap::Parser;
+use std::path::Path;
 use std::sync::Arc;
 use synapsemodel_tee_server::{
-    common::{AttestationResponse, HealthResponse, PublicKeyResponse},
-    AppState,
+    common::{verify_pcrs, AttestationProvider, Keystore, MockNsm, PcrPolicy, ProviderKind},
+    config::{ConfigArgs, ListenerKind},
+    models::ModelLoader,
+    server, AppState, Config, ServerBuilder,
 };
-use tower_http::cors::{Any, CorsLayer};
 use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+#[derive(Debug, Parser)]
+#[command(
+    name = "synapsemodel-tee-server",
+    about = "SynapseModel inference server for TEEs"
+)]
+struct Cli {
+    #[command(flatten)]
+    config: ConfigArgs,
+
+    /// Print the effective configuration as TOML and exit
+    #[arg(long)]
+    print_config: bool,
+}
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
     // Load environment variables
     dotenv::dotenv().ok();
 
+    // Defaults, then the config file, environment and flags
+    let config = Config::load(&cli.config)?;
+    if cli.print_config {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+
     // Initialize tracing
     tracing_subscriber::registry()
         .with(
@@ -36,138 +52,175 @@ async fn main() -> anyhow::Result<()> {
 
     info!("Starting SynapseModel TEE Server v1.0.0");
 
-    // Generate ephemeral keypair
-    let eph_kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
-    let public_key_hex = hex::encode(eph_kp.public().as_bytes());
-    info!("Ephemeral public key: {}", public_key_hex);
+    // Load or create the signing keystore
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_millis() as u64;
+    let keystore = Keystore::open(
+        config.keys.keystore_path.as_deref(),
+        config.keys.sealing_key.as_deref(),
+        now_ms,
+    )?
+    .with_rotation_interval(config.keys.rotation_interval_ms());
+    let epoch = keystore.current_epoch();
+    info!(
+        "Signing public key: {} (key id {}, epoch {})",
+        epoch.public_key, epoch.key_id, epoch.epoch
+    );
 
     // Create application state
-    let state = Arc::new(AppState::new(eph_kp));
+    let provider = config.attestation.provider;
+    let attestation_provider: Arc<dyn AttestationProvider> = if provider == ProviderKind::Software {
+        // The test CA is new on every boot, so verifiers need its root from here
+        let nsm = MockNsm::new()?;
+        warn!(
+            "Using software attestation: documents are signed by a test CA with root certificate (DER, hex) {}",
+            hex::encode(nsm.root_certificate_der())
+        );
+        Arc::new(nsm)
+    } else {
+        provider.create()?
+    };
+    info!("Attestation provider: {}", provider);
+    let egress_proxy = start_egress_relay(&config).await?;
+    let server_config = config.server_config();
+    let state = ServerBuilder::new(server_config.clone())
+        .keystore(keystore)
+        .attestation_provider(attestation_provider)
+        .model_loader(ModelLoader::from_settings(
+            &config.models,
+            egress_proxy.as_deref(),
+        )?)
+        .build_state();
+
+    // Check our own measurements against the allowed releases
+    check_pcr_policy(&state, config.attestation.pcr_policy.as_deref())?;
+
+    // Load models up front so attestations cover them from the start
+    for model_id in state.model_loader.list_models() {
+        if let Err(e) = state.model_loader.load_model(&model_id).await {
+            warn!("Model {} not loaded: {}", model_id, e);
+        }
+    }
+    info!(
+        "Loaded models root: {}",
+        state.model_loader.measurements()?.merkle_root
+    );
 
     // Build router
-    let app = create_router(state);
-
-    // Get server address
-    let host = std::env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .unwrap_or(3000);
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-
-    info!("Server listening on {}:{}", host, port);
-    info!("Health check: http://{}:{}/health_check", host, port);
-    info!("Inference endpoint: http://{}:{}/process_data", host, port);
+    let app = server::router(state, &server_config);
 
     // Start server
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    match config.server.listener {
+        ListenerKind::Tcp => {
+            let listener = tokio::net::TcpListener::bind(config.bind_addr()).await?;
+            let addr = listener.local_addr()?;
+            info!("Server listening on {}", addr);
+            info!("Health check: http://{}/health_check", addr);
+            info!("Inference endpoint: http://{}/process_data", addr);
+            axum::serve(listener, app).await?;
+        }
+        ListenerKind::Vsock => serve_vsock(&config, app).await?,
+    }
 
     Ok(())
 }
 
-/// Create the application router
-fn create_router(state: Arc<AppState>) -> Router {
-    // CORS configuration
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
-        .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]);
-
-    Router::new()
-        // Health check
-        .route("/health_check", get(health_check))
-        // Public key endpoint
-        .route("/get_pk", get(public_key))
-        // Attestation endpoint
-        .route("/get_attestation", get(attestation))
-        // Root endpoint
-        .route("/", get(root))
-        // 404 handler
-        .fallback(not_found)
-        .layer(cors)
-        .with_state(state)
+#[cfg(target_os = "linux")]
+async fn serve_vsock(config: &Config, app: axum::Router) -> anyhow::Result<()> {
+    use synapsemodel_tee_server::vsock::{self, VsockListener};
+
+    let listener = VsockListener::bind(config.server.vsock_cid, config.server.vsock_port)?;
+    let addr = listener.local_addr()?;
+    info!(
+        "Server listening on vsock cid {} port {}",
+        addr.cid(),
+        addr.port()
+    );
+    vsock::serve(listener, app).await?;
+    Ok(())
 }
 
-/// Root endpoint (ping)
-async fn root() -> &'static str {
-    "Pong!"
+#[cfg(not(target_os = "linux"))]
+async fn serve_vsock(_config: &Config, _app: axum::Router) -> anyhow::Result<()> {
+    anyhow::bail!("vsock is only supported on Linux")
 }
 
-/// Health check endpoint
-async fn health_check() -> impl IntoResponse {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    Json(HealthResponse {
-        status: "healthy".to_string(),
-        timestamp,
-        version: "1.0.0".to_string(),
-    })
+/// Relay outbound requests to the parent's egress proxy when configured;
+/// returns the local proxy URL
+#[cfg(target_os = "linux")]
+async fn start_egress_relay(config: &Config) -> anyhow::Result<Option<String>> {
+    if config.egress_proxy_url().is_none() {
+        return Ok(None);
+    }
+    let egress = &config.egress;
+    let url = synapsemodel_tee_server::egress::spawn_relay(
+        egress.proxy_addr,
+        egress.vsock_cid,
+        egress.vsock_port,
+    )
+    .await?;
+    info!(
+        "Outbound requests go through {} to vsock cid {} port {}",
+        url, egress.vsock_cid, egress.vsock_port
+    );
+    Ok(Some(url))
 }
 
-/// Public key endpoint
-async fn public_key(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    let public_key_hex = hex::encode(state.eph_kp.public().as_bytes());
-
-    Json(PublicKeyResponse {
-        public_key: public_key_hex,
-        format: "ed25519-hex".to_string(),
-    })
+#[cfg(not(target_os = "linux"))]
+async fn start_egress_relay(config: &Config) -> anyhow::Result<Option<String>> {
+    match config.egress_proxy_url() {
+        Some(_) => anyhow::bail!("vsock is only supported on Linux"),
+        None => Ok(None),
+    }
 }
 
-/// Attestation endpoint
-async fn attestation() -> impl IntoResponse {
-    use synapsemodel_tee_server::common::get_attestation_document;
-
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
+/// Verify PCRs against the policy at `policy_path`; release builds refuse to start on failure
+fn check_pcr_policy(state: &AppState, policy_path: Option<&Path>) -> anyhow::Result<()> {
+    let provider = state.attestation.kind();
+    if !provider.has_pcrs() {
+        warn!("{} attestation has no PCRs, skipping PCR policy", provider);
+        return Ok(());
+    }
 
-    match get_attestation_document() {
-        Ok(doc) => {
-            let attestation_hex = hex::encode(doc);
-            (
-                StatusCode::OK,
-                Json(AttestationResponse {
-                    attestation: attestation_hex,
-                    timestamp,
-                }),
-            )
+    let Some(policy_path) = policy_path else {
+        if cfg!(debug_assertions) {
+            warn!("PCR_POLICY_PATH not set, skipping PCR verification in development mode");
+            return Ok(());
         }
-        Err(e) => {
-            warn!("Attestation not available: {}", e);
-            (
-                StatusCode::SERVICE_UNAVAILABLE,
-                Json(AttestationResponse {
-                    attestation: "Attestation not available in development mode".to_string(),
-                    timestamp,
-                }),
-            )
+        anyhow::bail!("PCR_POLICY_PATH must be set in release builds");
+    };
+    let policy = PcrPolicy::load(policy_path)?;
+    if let Some(release) = policy.releases.iter().find(|release| release.is_debug()) {
+        if !cfg!(debug_assertions) {
+            anyhow::bail!(
+                "PCR policy release {} has all-zero PCRs, which any debug-mode enclave reports; \
+                 keep it in a dev-only policy",
+                release.name
+            );
         }
+        warn!(
+            "PCR policy release {} matches debug-mode enclaves",
+            release.name
+        );
     }
-}
-
-/// 404 handler
-async fn not_found() -> impl IntoResponse {
-    (
-        StatusCode::NOT_FOUND,
-        Json(serde_json::json!({
-            "error": "Not found"
-        })),
-    )
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
 
-    #[tokio::test]
-    async fn test_health_check() {
-        let response = health_check().await.into_response();
-        assert_eq!(response.status(), StatusCode::OK);
+    let failure = match verify_pcrs(state.attestation.as_ref(), &policy) {
+        Ok(verdict) if verdict.is_allowed() => {
+            info!("{}", verdict);
+            return Ok(());
+        }
+        Ok(verdict) => verdict.to_string(),
+        Err(e) => e.to_string(),
+    };
+
+    if cfg!(debug_assertions) {
+        warn!(
+            "PCR verification failed (ignored in development mode): {}",
+            failure
+        );
+        Ok(())
+    } else {
+        anyhow::bail!("PCR verification failed: {}", failure)
     }
 }
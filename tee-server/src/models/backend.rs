@@ -0,0 +1,377 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Inference engines behind [`ModelLoader`](crate::models::ModelLoader)
+//!
+//! Every engine implements [`InferenceBackend`]; callers only see the trait,
+//! so a model can move between engines without touching endpoint code.
+
+use crate::models::Tensor;
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Name and declared shape of a graph input or output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorInfo {
+    pub name: String,
+    /// `-1` for dynamic dimensions
+    pub shape: Vec<i64>,
+}
+
+/// A loaded model, ready to run on one engine
+pub trait InferenceBackend: Send + Sync {
+    /// Parse a model from `bytes`, read from `path`, which only names it
+    fn from_bytes(path: &Path, bytes: &[u8]) -> Result<Self>
+    where
+        Self: Sized;
+
+    /// Load the model file at `path`
+    fn load(path: &Path) -> Result<Self>
+    where
+        Self: Sized,
+    {
+        Self::from_bytes(path, &read_model(path)?)
+    }
+
+    fn kind(&self) -> BackendKind;
+
+    fn inputs(&self) -> &[TensorInfo];
+
+    fn outputs(&self) -> &[TensorInfo];
+
+    /// Run the graph, returning every output named after [`Self::outputs`]
+    ///
+    /// Named inputs are matched to graph inputs by name, unnamed ones by
+    /// position; see [`bind_inputs`].
+    fn run(&self, inputs: &[Tensor]) -> Result<Vec<Tensor>>;
+
+    /// Approximate resident size of the loaded model in bytes
+    fn memory_footprint(&self) -> usize;
+}
+
+/// Engine that executes a model's graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// ONNX Runtime (`onnx-ort` feature)
+    Ort,
+    /// tract, pure Rust (`onnx-tract` feature)
+    Tract,
+    /// Fabricated outputs (`mock-inference` feature)
+    Mock,
+}
+
+impl BackendKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackendKind::Ort => "ort",
+            BackendKind::Tract => "tract",
+            BackendKind::Mock => "mock",
+        }
+    }
+
+    /// Cargo feature that compiles this engine in
+    pub fn feature(&self) -> &'static str {
+        match self {
+            BackendKind::Ort => "onnx-ort",
+            BackendKind::Tract => "onnx-tract",
+            BackendKind::Mock => "mock-inference",
+        }
+    }
+
+    /// Load the model file at `path` on this engine
+    pub fn load(&self, path: &Path) -> Result<Box<dyn InferenceBackend>> {
+        self.from_bytes(path, &read_model(path)?)
+    }
+
+    /// Parse a model already read from `path` on this engine
+    #[cfg_attr(
+        not(any(feature = "onnx-ort", feature = "onnx-tract", feature = "mock-inference")),
+        allow(unused_variables)
+    )]
+    pub fn from_bytes(&self, path: &Path, bytes: &[u8]) -> Result<Box<dyn InferenceBackend>> {
+        match self {
+            #[cfg(feature = "onnx-ort")]
+            BackendKind::Ort => Ok(Box::new(crate::models::OrtBackend::from_bytes(
+                path, bytes,
+            )?)),
+            #[cfg(feature = "onnx-tract")]
+            BackendKind::Tract => Ok(Box::new(crate::models::TractBackend::from_bytes(
+                path, bytes,
+            )?)),
+            #[cfg(feature = "mock-inference")]
+            BackendKind::Mock => Ok(Box::new(crate::models::MockBackend::from_bytes(
+                path, bytes,
+            )?)),
+            #[allow(unreachable_patterns)]
+            other => Err(EnclaveError::InferenceError(format!(
+                "Inference backend {} is not compiled in: build with the `{}` feature",
+                other,
+                other.feature()
+            ))),
+        }
+    }
+
+    pub fn is_compiled(&self) -> bool {
+        match self {
+            BackendKind::Ort => cfg!(feature = "onnx-ort"),
+            BackendKind::Tract => cfg!(feature = "onnx-tract"),
+            BackendKind::Mock => cfg!(feature = "mock-inference"),
+        }
+    }
+
+    /// Parse `MODEL_BACKENDS`, e.g. `mnist-classifier=tract,sentiment-analysis=ort`
+    pub fn parse_assignments(value: &str) -> Result<Vec<(String, BackendKind)>> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let (model_id, backend) = entry.split_once('=').ok_or_else(|| {
+                    EnclaveError::ValidationError(format!(
+                        "Invalid MODEL_BACKENDS entry {:?}, expected <model_id>=<backend>",
+                        entry
+                    ))
+                })?;
+                Ok((model_id.trim().to_string(), backend.trim().parse()?))
+            })
+            .collect()
+    }
+}
+
+impl Default for BackendKind {
+    /// ONNX Runtime if compiled in, then tract, then the mock
+    fn default() -> Self {
+        [BackendKind::Ort, BackendKind::Tract, BackendKind::Mock]
+            .into_iter()
+            .find(BackendKind::is_compiled)
+            .unwrap_or(BackendKind::Ort)
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = EnclaveError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "ort" | "onnxruntime" => Ok(BackendKind::Ort),
+            "tract" => Ok(BackendKind::Tract),
+            "mock" => Ok(BackendKind::Mock),
+            other => Err(EnclaveError::ValidationError(format!(
+                "Unknown inference backend {:?} (expected ort, tract or mock)",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Resolve dynamic (`-1`) dimensions so the shape holds `len` elements
+///
+/// At most one dimension may be dynamic; a leading batch dimension is
+/// resolved to whatever the flat input implies.
+pub fn concrete_shape(declared: &[i64], len: usize) -> Result<Vec<i64>> {
+    let known = declared
+        .iter()
+        .filter(|&&dim| dim > 0)
+        .try_fold(1i64, |product, &dim| product.checked_mul(dim))
+        .ok_or_else(|| {
+            EnclaveError::ValidationError(format!("Model shape {:?} is too large", declared))
+        })?;
+    let dynamic = declared.iter().filter(|&&dim| dim <= 0).count();
+    let len = len as i64;
+
+    let fits = match dynamic {
+        0 => known == len,
+        1 => known > 0 && len % known == 0,
+        _ => false,
+    };
+    if !fits {
+        return Err(EnclaveError::ValidationError(format!(
+            "Input of {} values does not fit model shape {:?}",
+            len, declared
+        )));
+    }
+
+    Ok(declared
+        .iter()
+        .map(|&dim| if dim > 0 { dim } else { len / known })
+        .collect())
+}
+
+/// Order `inputs` like the graph's `declared` inputs and check their shapes
+///
+/// Either every input is named, or none is and they are taken in order.
+/// Declared dimensions of `-1` accept any size.
+pub fn bind_inputs<'a>(declared: &[TensorInfo], inputs: &'a [Tensor]) -> Result<Vec<&'a Tensor>> {
+    let positional = inputs.iter().all(|input| input.name.is_empty());
+    if positional && inputs.len() != declared.len() {
+        return Err(EnclaveError::ValidationError(format!(
+            "Model takes {} inputs, got {}",
+            declared.len(),
+            inputs.len()
+        )));
+    }
+    if let Some(unknown) = inputs
+        .iter()
+        .find(|input| !positional && !declared.iter().any(|info| info.name == input.name))
+    {
+        return Err(EnclaveError::ValidationError(format!(
+            "Model has no input named {:?}",
+            unknown.name
+        )));
+    }
+    for (position, input) in inputs.iter().enumerate().filter(|_| !positional) {
+        if inputs[..position]
+            .iter()
+            .any(|seen| seen.name == input.name)
+        {
+            return Err(EnclaveError::ValidationError(format!(
+                "Input {:?} is given more than once",
+                input.name
+            )));
+        }
+    }
+
+    declared
+        .iter()
+        .enumerate()
+        .map(|(position, info)| {
+            let input = if positional {
+                &inputs[position]
+            } else {
+                inputs
+                    .iter()
+                    .find(|input| input.name == info.name)
+                    .ok_or_else(|| {
+                        EnclaveError::ValidationError(format!("Missing input {:?}", info.name))
+                    })?
+            };
+
+            let fits = info.shape.len() == input.shape().len()
+                && info
+                    .shape
+                    .iter()
+                    .zip(input.shape())
+                    .all(|(&want, &got)| want < 0 || want as usize == got);
+            if !fits {
+                return Err(EnclaveError::ValidationError(format!(
+                    "Input {:?} has shape {:?}, model expects {:?}",
+                    info.name,
+                    input.shape(),
+                    info.shape
+                )));
+            }
+            Ok(input)
+        })
+        .collect()
+}
+
+/// Contents of the model file at `path`
+pub fn read_model(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| {
+        EnclaveError::ModelNotFound(format!("Model file {} not readable: {}", path.display(), e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignments() {
+        let parsed =
+            BackendKind::parse_assignments("mnist-classifier=tract, sentiment-analysis=ORT,")
+                .unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                ("mnist-classifier".to_string(), BackendKind::Tract),
+                ("sentiment-analysis".to_string(), BackendKind::Ort),
+            ]
+        );
+        assert!(BackendKind::parse_assignments("mnist-classifier").is_err());
+        assert!(BackendKind::parse_assignments("mnist-classifier=tflite").is_err());
+    }
+
+    #[test]
+    fn test_concrete_shape() {
+        assert_eq!(
+            concrete_shape(&[-1, 1, 28, 28], 784).unwrap(),
+            vec![1, 1, 28, 28]
+        );
+        assert_eq!(concrete_shape(&[-1, 4], 8).unwrap(), vec![2, 4]);
+        assert_eq!(concrete_shape(&[1, 784], 784).unwrap(), vec![1, 784]);
+        assert!(concrete_shape(&[1, 784], 100).is_err());
+        assert!(concrete_shape(&[-1, -1], 4).is_err());
+        assert!(matches!(
+            concrete_shape(&[i64::MAX, 2, -1], 4),
+            Err(EnclaveError::ValidationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let missing = Path::new("/nonexistent/model.onnx");
+        for kind in [BackendKind::Ort, BackendKind::Tract, BackendKind::Mock] {
+            assert!(matches!(
+                kind.load(missing),
+                Err(EnclaveError::ModelNotFound(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_bind_inputs() {
+        use crate::models::TensorData;
+
+        let declared = [
+            TensorInfo {
+                name: "ids".to_string(),
+                shape: vec![-1, 8],
+            },
+            TensorInfo {
+                name: "mask".to_string(),
+                shape: vec![-1, 8],
+            },
+        ];
+        let tensor = |name: &str, shape: Vec<usize>| {
+            let len = shape.iter().product();
+            Tensor::new(name, shape, TensorData::I64(vec![0; len])).unwrap()
+        };
+
+        let named = [tensor("mask", vec![2, 8]), tensor("ids", vec![2, 8])];
+        let bound = bind_inputs(&declared, &named).unwrap();
+        assert_eq!(bound[0].name, "ids");
+        assert_eq!(bound[1].name, "mask");
+
+        let positional = [tensor("", vec![1, 8]), tensor("", vec![1, 8])];
+        assert_eq!(bind_inputs(&declared, &positional).unwrap().len(), 2);
+
+        assert!(bind_inputs(&declared, &positional[..1]).is_err());
+        assert!(bind_inputs(&declared, &[tensor("ids", vec![1, 8])]).is_err());
+        assert!(bind_inputs(
+            &declared,
+            &[tensor("ids", vec![1, 8]), tensor("extra", vec![1])]
+        )
+        .is_err());
+        assert!(bind_inputs(&declared, &[tensor("", vec![8]), tensor("", vec![1, 8])]).is_err());
+        assert!(bind_inputs(
+            &declared,
+            &[
+                tensor("ids", vec![1, 8]),
+                tensor("mask", vec![1, 8]),
+                tensor("ids", vec![2, 8])
+            ]
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,371 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Shared cache of loaded models
+//!
+//! Models are handed out as [`LoadedModel`]s, so a request keeps its model
+//! alive even if the cache drops it meanwhile, along with the measurement of
+//! the bytes it was built from. Each model counts its
+//! [`memory_footprint`](InferenceBackend::memory_footprint) against an
+//! optional byte budget; when a load would exceed it, the least recently
+//! used models that are not pinned are evicted first. The budget is checked
+//! once the model is loaded, so it can be exceeded while a load is running.
+//!
+//! Concurrent requests for a model that is not cached wait for one load
+//! instead of each reading the file. Loads run on tokio's blocking pool, so
+//! a cold model does not stall other requests.
+
+use crate::models::{InferenceBackend, ModelMeasurement};
+use crate::{EnclaveError, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+use tracing::{debug, info};
+
+/// Loaded model shared between requests
+pub type ModelHandle = Arc<dyn InferenceBackend>;
+
+/// Model and the measurement of exactly the bytes it was loaded from
+#[derive(Clone)]
+pub struct LoadedModel {
+    pub model: ModelHandle,
+    pub measurement: ModelMeasurement,
+}
+
+/// LRU cache of loaded models with a memory budget
+pub struct ModelCache {
+    /// `None` keeps every model loaded
+    budget_bytes: Option<usize>,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    models: HashMap<String, CachedModel>,
+    /// Loads in progress, shared by every request for the model
+    loading: HashMap<String, Arc<OnceCell<LoadedModel>>>,
+    /// Bumped on every lookup to order models by last use
+    clock: u64,
+    /// Evicted since the last `take_evicted` and not cached again
+    evicted: HashSet<String>,
+}
+
+struct CachedModel {
+    model: LoadedModel,
+    size_bytes: usize,
+    pinned: bool,
+    last_used: u64,
+}
+
+impl CacheState {
+    fn used_bytes(&self) -> usize {
+        self.models.values().map(|cached| cached.size_bytes).sum()
+    }
+
+    fn pinned_bytes(&self) -> usize {
+        self.models
+            .values()
+            .filter(|cached| cached.pinned)
+            .map(|cached| cached.size_bytes)
+            .sum()
+    }
+
+    /// Least recently used model that may be evicted
+    fn eviction_candidate(&self) -> Option<String> {
+        self.models
+            .iter()
+            .filter(|(_, cached)| !cached.pinned)
+            .min_by_key(|(_, cached)| cached.last_used)
+            .map(|(model_id, _)| model_id.clone())
+    }
+}
+
+impl ModelCache {
+    pub fn new(budget_bytes: Option<usize>) -> Self {
+        Self {
+            budget_bytes,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    pub fn budget_bytes(&self) -> Option<usize> {
+        self.budget_bytes
+    }
+
+    /// Bytes held by cached models
+    pub fn used_bytes(&self) -> usize {
+        self.state.lock().unwrap().used_bytes()
+    }
+
+    /// Ids of the cached models, sorted
+    pub fn cached_models(&self) -> Vec<String> {
+        let mut model_ids: Vec<_> = self.state.lock().unwrap().models.keys().cloned().collect();
+        model_ids.sort();
+        model_ids
+    }
+
+    /// Ids of the models evicted since the last call, sorted
+    ///
+    /// Models loaded again since their eviction are left out.
+    pub fn take_evicted(&self) -> Vec<String> {
+        let mut model_ids: Vec<_> = self.state.lock().unwrap().evicted.drain().collect();
+        model_ids.sort();
+        model_ids
+    }
+
+    /// Cached `model_id`, or the result of `load` once it fits the budget
+    ///
+    /// `pinned` models are never evicted. If another request is already
+    /// loading `model_id`, this waits for it instead of calling `load`; if
+    /// that load fails, the next waiter tries its own. `load` runs on the
+    /// blocking pool.
+    pub async fn get_or_load(
+        &self,
+        model_id: &str,
+        pinned: bool,
+        load: impl FnOnce() -> Result<LoadedModel> + Send + 'static,
+    ) -> Result<LoadedModel> {
+        let loading = {
+            let mut state = self.state.lock().unwrap();
+            state.clock += 1;
+            let clock = state.clock;
+            if let Some(cached) = state.models.get_mut(model_id) {
+                cached.last_used = clock;
+                debug!("Model {} loaded from cache", model_id);
+                return Ok(cached.model.clone());
+            }
+            state
+                .loading
+                .entry(model_id.to_string())
+                .or_default()
+                .clone()
+        };
+
+        let model = loading
+            .get_or_try_init(|| async {
+                let model = tokio::task::spawn_blocking(load).await.map_err(|e| {
+                    EnclaveError::InferenceError(format!("Loading {} failed: {}", model_id, e))
+                })??;
+                // Cached before the waiters wake, so later requests hit the cache
+                let mut state = self.state.lock().unwrap();
+                self.insert(&mut state, model_id, model.clone(), pinned)?;
+                state.loading.remove(model_id);
+                Ok::<_, EnclaveError>(model)
+            })
+            .await?;
+        Ok(model.clone())
+    }
+
+    fn insert(
+        &self,
+        state: &mut CacheState,
+        model_id: &str,
+        model: LoadedModel,
+        pinned: bool,
+    ) -> Result<()> {
+        let size_bytes = model.model.memory_footprint();
+        if let Some(budget) = self.budget_bytes {
+            let pinned_bytes = state.pinned_bytes();
+            if pinned_bytes + size_bytes > budget {
+                return Err(EnclaveError::InferenceError(format!(
+                    "Model {} needs {} bytes, the model cache budget is {} bytes with {} pinned",
+                    model_id, size_bytes, budget, pinned_bytes
+                )));
+            }
+            while state.used_bytes() + size_bytes > budget {
+                let Some(evicted_id) = state.eviction_candidate() else {
+                    break;
+                };
+                let evicted = state.models.remove(&evicted_id).unwrap();
+                info!(
+                    "Evicted model {} ({} bytes) from the model cache",
+                    evicted_id, evicted.size_bytes
+                );
+                state.evicted.insert(evicted_id);
+            }
+        }
+
+        info!(
+            "Cached model {} ({} bytes{})",
+            model_id,
+            size_bytes,
+            if pinned { ", pinned" } else { "" }
+        );
+        state.evicted.remove(model_id);
+        state.models.insert(
+            model_id.to_string(),
+            CachedModel {
+                model,
+                size_bytes,
+                pinned,
+                last_used: state.clock,
+            },
+        );
+        Ok(())
+    }
+}
+
+impl Default for ModelCache {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{BackendKind, Tensor, TensorInfo};
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Model that only reports a footprint
+    struct Footprint(usize);
+
+    impl InferenceBackend for Footprint {
+        fn from_bytes(_path: &Path, _bytes: &[u8]) -> Result<Self> {
+            Ok(Self(0))
+        }
+
+        fn kind(&self) -> BackendKind {
+            BackendKind::Mock
+        }
+
+        fn inputs(&self) -> &[TensorInfo] {
+            &[]
+        }
+
+        fn outputs(&self) -> &[TensorInfo] {
+            &[]
+        }
+
+        fn run(&self, _inputs: &[Tensor]) -> Result<Vec<Tensor>> {
+            Ok(Vec::new())
+        }
+
+        fn memory_footprint(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn footprint(size_bytes: usize) -> LoadedModel {
+        LoadedModel {
+            model: Arc::new(Footprint(size_bytes)),
+            measurement: ModelMeasurement::of_bytes("m", Path::new("m.onnx"), b""),
+        }
+    }
+
+    async fn load(
+        cache: &ModelCache,
+        model_id: &str,
+        size_bytes: usize,
+        pinned: bool,
+    ) -> LoadedModel {
+        cache
+            .get_or_load(model_id, pinned, move || Ok(footprint(size_bytes)))
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_share_the_model() {
+        let cache = ModelCache::default();
+        let first = load(&cache, "a", 10, false).await;
+        let second = cache
+            .get_or_load("a", false, || panic!("cached model reloaded"))
+            .await
+            .unwrap();
+        assert!(Arc::ptr_eq(&first.model, &second.model));
+        assert_eq!(cache.used_bytes(), 10);
+    }
+
+    #[tokio::test]
+    async fn test_lru_eviction_skips_pinned_models() {
+        let cache = ModelCache::new(Some(100));
+        load(&cache, "pinned", 40, true).await;
+        load(&cache, "a", 30, false).await;
+        load(&cache, "b", 30, false).await;
+        // Touch `a` so `b` is the least recently used
+        load(&cache, "a", 30, false).await;
+
+        load(&cache, "c", 30, false).await;
+        assert_eq!(cache.cached_models(), ["a", "c", "pinned"]);
+        assert_eq!(cache.take_evicted(), ["b"]);
+        load(&cache, "d", 60, false).await;
+        assert_eq!(cache.cached_models(), ["d", "pinned"]);
+        assert_eq!(cache.used_bytes(), 100);
+        assert_eq!(cache.take_evicted(), ["a", "c"]);
+        assert!(cache.take_evicted().is_empty());
+
+        let too_big = cache.get_or_load("e", false, || Ok(footprint(61))).await;
+        assert!(matches!(too_big, Err(EnclaveError::InferenceError(_))));
+        assert_eq!(cache.cached_models(), ["d", "pinned"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_misses_load_once() {
+        let cache = Arc::new(ModelCache::default());
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let requests: Vec<_> = (0..8)
+            .map(|_| {
+                let (cache, loads) = (cache.clone(), loads.clone());
+                tokio::spawn(async move {
+                    cache
+                        .get_or_load("cold", false, move || {
+                            loads.fetch_add(1, Ordering::SeqCst);
+                            std::thread::sleep(Duration::from_millis(50));
+                            Ok(footprint(1))
+                        })
+                        .await
+                        .unwrap()
+                })
+            })
+            .collect();
+        let mut models = Vec::new();
+        for request in requests {
+            models.push(request.await.unwrap());
+        }
+
+        assert_eq!(loads.load(Ordering::SeqCst), 1);
+        assert!(models
+            .iter()
+            .all(|loaded| Arc::ptr_eq(&loaded.model, &models[0].model)));
+    }
+
+    #[tokio::test]
+    async fn test_load_does_not_block_the_runtime() {
+        // One worker thread: a load running on it would stall the timer below
+        let cache = Arc::new(ModelCache::default());
+        let slow = tokio::spawn({
+            let cache = cache.clone();
+            async move {
+                cache
+                    .get_or_load("slow", false, || {
+                        std::thread::sleep(Duration::from_millis(300));
+                        Ok(footprint(1))
+                    })
+                    .await
+            }
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!slow.is_finished());
+        assert!(cache.cached_models().is_empty());
+        slow.await.unwrap().unwrap();
+        assert_eq!(cache.cached_models(), ["slow"]);
+    }
+
+    #[tokio::test]
+    async fn test_failed_load_is_retried() {
+        let cache = ModelCache::default();
+        let failed = cache
+            .get_or_load("a", false, || {
+                Err(EnclaveError::InferenceError("corrupt".to_string()))
+            })
+            .await;
+        assert!(failed.is_err());
+        assert!(cache.cached_models().is_empty());
+        load(&cache, "a", 1, false).await;
+        assert_eq!(cache.cached_models(), ["a"]);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Model manifest: which models `MODELS_DIR` serves and how to call them
+//!
+//! `models.toml` (or `models.json`) next to the model files lists one entry
+//! per model, so adding a model needs no code changes:
+//!
+//! ```toml
+//! [[model]]
+//! id = "mnist-classifier"
+//! file = "mnist.onnx"
+//! version = "v1.0.0"         # optional, signed as `model_version`
+//! backend = "tract"          # optional, see `BackendKind`
+//! sha256 = "..."             # optional, hex SHA-256 of the file
+//! blake2b256 = "..."         # optional, hex Blake2b-256 of the file
+//! pinned = true              # optional, never evicted from the model cache
+//! description = "Handwritten digit classification"
+//! labels = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+//!
+//! [model.input]              # optional, raw tensors are always accepted
+//! field = "pixels"
+//! aliases = ["data"]
+//! dtype = "f32"
+//! shape = [1, 1, 28, 28]     # at most one -1 (dynamic) dimension
+//!
+//! [model.preprocessing]
+//! kind = "scale_to_unit"
+//!
+//! [model.postprocessing]
+//! kind = "classification"
+//! ```
+
+use crate::models::{BackendKind, DType};
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Component, Path};
+use tracing::warn;
+
+/// Manifest file names looked up in `MODELS_DIR`, in order
+pub const MANIFEST_FILES: [&str; 2] = ["models.toml", "models.json"];
+
+/// Models served from one directory, loaded from TOML or JSON
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelManifest {
+    #[serde(rename = "model", default)]
+    pub models: Vec<ModelEntry>,
+}
+
+/// One model and how requests map onto its graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelEntry {
+    pub id: String,
+    /// File name relative to `MODELS_DIR`
+    pub file: String,
+    /// Release label signed into responses as `model_version`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Engine to run on, `BackendKind::default()` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend: Option<BackendKind>,
+    /// Expected hex-encoded SHA-256 of the file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// Expected hex-encoded Blake2b-256 of the file, as hashed on Sui
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blake2b256: Option<String>,
+    /// Keep the model cached however the memory budget is used
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// How to build the input tensor from a JSON request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<InputSpec>,
+    #[serde(default)]
+    pub preprocessing: Preprocessing,
+    #[serde(default)]
+    pub postprocessing: Postprocessing,
+    /// Class names, indexed like the output probabilities
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+}
+
+/// Request field that holds the model input
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSpec {
+    /// Key in `input_data`, e.g. `pixels`
+    pub field: String,
+    /// Other keys accepted for the same field
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    pub dtype: DType,
+    /// `-1` marks the dimension inferred from the number of values; empty
+    /// takes the shape of the model's first input
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shape: Vec<i64>,
+}
+
+/// Transformation of numeric input values before inference
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Preprocessing {
+    #[default]
+    None,
+    /// Values above 1 are taken as `[0, 255]` and divided by 255
+    ScaleToUnit,
+    /// `(value - mean) / std`
+    Normalize { mean: f32, std: f32 },
+}
+
+/// Shape of the inference `result`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Postprocessing {
+    /// Every output tensor, as `{"outputs": [...]}`
+    #[default]
+    None,
+    /// Arg-max of the first output, with `labels` if listed
+    Classification {
+        /// Apply softmax first, for models that output logits
+        #[serde(default)]
+        softmax: bool,
+    },
+}
+
+impl Preprocessing {
+    pub fn apply(&self, values: &mut [f32]) {
+        match self {
+            Preprocessing::None => {}
+            Preprocessing::ScaleToUnit => {
+                for value in values.iter_mut().filter(|value| **value > 1.0) {
+                    *value /= 255.0;
+                }
+            }
+            Preprocessing::Normalize { mean, std } => {
+                for value in values.iter_mut() {
+                    *value = (*value - mean) / std;
+                }
+            }
+        }
+    }
+}
+
+impl ModelManifest {
+    pub fn from_toml_str(s: &str) -> Result<Self> {
+        let manifest: Self =
+            toml::from_str(s).map_err(|e| invalid_manifest(&format!("invalid TOML: {}", e)))?;
+        manifest.validate()
+    }
+
+    pub fn from_json_str(s: &str) -> Result<Self> {
+        let manifest: Self = serde_json::from_str(s)
+            .map_err(|e| invalid_manifest(&format!("invalid JSON: {}", e)))?;
+        manifest.validate()
+    }
+
+    /// Load a manifest file; `.json` files are parsed as JSON, anything else as TOML
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| invalid_manifest(&format!("failed to read {:?}: {}", path, e)))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json_str(&contents),
+            _ => Self::from_toml_str(&contents),
+        }
+    }
+
+    /// Load the first of [`MANIFEST_FILES`] in `models_dir`; no manifest lists no models
+    pub fn load_dir(models_dir: impl AsRef<Path>) -> Result<Self> {
+        let models_dir = models_dir.as_ref();
+        match MANIFEST_FILES
+            .iter()
+            .map(|name| models_dir.join(name))
+            .find(|path| path.exists())
+        {
+            Some(path) => Self::load(path),
+            None => {
+                warn!("No model manifest in {:?}, serving no models", models_dir);
+                Ok(Self::default())
+            }
+        }
+    }
+
+    fn validate(self) -> Result<Self> {
+        let mut ids = HashSet::new();
+        for entry in &self.models {
+            if entry.id.is_empty() {
+                return Err(invalid_manifest("model with an empty id"));
+            }
+            if !ids.insert(entry.id.as_str()) {
+                return Err(invalid_manifest(&format!(
+                    "duplicate model id {}",
+                    entry.id
+                )));
+            }
+            let plain_file = !entry.file.is_empty()
+                && Path::new(&entry.file)
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)));
+            if !plain_file {
+                return Err(invalid_manifest(&format!(
+                    "model {} file {:?} must be a relative path inside MODELS_DIR",
+                    entry.id, entry.file
+                )));
+            }
+            for (field, digest) in [("sha256", &entry.sha256), ("blake2b256", &entry.blake2b256)] {
+                if let Some(digest) = digest {
+                    if digest.len() != 64 || !digest.bytes().all(|b| b.is_ascii_hexdigit()) {
+                        return Err(invalid_manifest(&format!(
+                            "model {} {} is not 32 hex-encoded bytes",
+                            entry.id, field
+                        )));
+                    }
+                }
+            }
+            if let Some(input) = &entry.input {
+                if input.shape.iter().filter(|&&dim| dim < 0).count() > 1 {
+                    return Err(invalid_manifest(&format!(
+                        "model {} input shape may have at most one -1 dimension",
+                        entry.id
+                    )));
+                }
+                if entry.preprocessing != Preprocessing::None && input.dtype != DType::F32 {
+                    return Err(invalid_manifest(&format!(
+                        "model {} preprocessing needs an f32 input, not {}",
+                        entry.id, input.dtype
+                    )));
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+fn invalid_manifest(reason: &str) -> EnclaveError {
+    EnclaveError::GenericError(format!("Invalid model manifest: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+        [[model]]
+        id = "mnist-classifier"
+        file = "mnist.onnx"
+        version = "v1.0.0"
+        backend = "tract"
+        sha256 = "9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08"
+        pinned = true
+        labels = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]
+
+        [model.input]
+        field = "pixels"
+        aliases = ["data"]
+        dtype = "f32"
+        shape = [1, 1, 28, 28]
+
+        [model.preprocessing]
+        kind = "scale_to_unit"
+
+        [model.postprocessing]
+        kind = "classification"
+
+        [[model]]
+        id = "embedder"
+        file = "text/embedder.onnx"
+    "#;
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = ModelManifest::from_toml_str(MANIFEST).unwrap();
+        assert_eq!(manifest.models.len(), 2);
+
+        let mnist = &manifest.models[0];
+        assert_eq!(mnist.version.as_deref(), Some("v1.0.0"));
+        assert_eq!(mnist.backend, Some(BackendKind::Tract));
+        assert!(mnist.pinned);
+        assert_eq!(mnist.input.as_ref().unwrap().dtype, DType::F32);
+        assert_eq!(mnist.preprocessing, Preprocessing::ScaleToUnit);
+        assert_eq!(
+            mnist.postprocessing,
+            Postprocessing::Classification { softmax: false }
+        );
+
+        let embedder = &manifest.models[1];
+        assert_eq!(embedder.input, None);
+        assert_eq!(embedder.version, None);
+        assert!(!embedder.pinned);
+        assert_eq!(embedder.postprocessing, Postprocessing::None);
+
+        let json = serde_json::to_string(&manifest).unwrap();
+        assert_eq!(ModelManifest::from_json_str(&json).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_invalid_manifests() {
+        let entry = |extra: &str| format!("[[model]]\nid = \"m\"\nfile = \"m.onnx\"\n{}", extra);
+        for invalid in [
+            format!("{}\n{}", entry(""), entry("")),
+            "[[model]]\nid = \"m\"\nfile = \"../secret.onnx\"".to_string(),
+            "[[model]]\nid = \"m\"\nfile = \"/etc/m.onnx\"".to_string(),
+            entry("sha256 = \"abc\""),
+            entry(&format!("blake2b256 = \"{}\"", "g".repeat(64))),
+            entry("backend = \"tflite\""),
+            entry("[model.input]\nfield = \"x\"\ndtype = \"f32\"\nshape = [-1, -1]"),
+            entry(
+                "[model.input]\nfield = \"text\"\ndtype = \"string\"\nshape = [1]\n\
+                 [model.preprocessing]\nkind = \"scale_to_unit\"",
+            ),
+        ] {
+            assert!(
+                ModelManifest::from_toml_str(&invalid).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_preprocessing() {
+        let mut pixels = vec![255.0; 784];
+        pixels[0] = 0.5;
+        Preprocessing::ScaleToUnit.apply(&mut pixels);
+        assert_eq!(pixels.len(), 784);
+        assert!(pixels.iter().all(|&p| (0.0..=1.0).contains(&p)));
+        assert_eq!(pixels[0], 0.5);
+
+        let mut values = vec![1.0, 3.0];
+        Preprocessing::Normalize {
+            mean: 1.0,
+            std: 2.0,
+        }
+        .apply(&mut values);
+        assert_eq!(values, vec![0.0, 1.0]);
+    }
+}
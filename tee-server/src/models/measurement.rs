@@ -0,0 +1,254 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Measurement of the loaded model files
+//!
+//! Every loaded file is hashed with SHA-256 and the hashes are combined
+//! into a Merkle root that the enclave puts in its attestation `user_data`,
+//! so buyers can check which weights answered their requests. Files are
+//! also hashed with Blake2b-256, the digest Sui uses for on-chain objects,
+//! so either can be checked against the expected digests before the model
+//! is loaded.
+//!
+//! Leaves are sorted by model id. A leaf is
+//! `SHA-256(0x00 || u32_be(len(model_id)) || model_id || file_sha256)`, an
+//! inner node is `SHA-256(0x01 || left || right)`, and an odd node at the
+//! end of a level moves up unchanged. The root of no models is `SHA-256("")`.
+
+use crate::models::read_model;
+use crate::{EnclaveError, Result};
+use fastcrypto::hash::{Blake2b256, HashFunction};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Hash of one loaded model file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelMeasurement {
+    pub model_id: String,
+    /// File name relative to `MODELS_DIR`
+    pub file: String,
+    /// Hex-encoded SHA-256 of the file contents
+    pub sha256: String,
+    /// Hex-encoded Blake2b-256 of the file contents
+    pub blake2b256: String,
+    pub size_bytes: u64,
+}
+
+impl ModelMeasurement {
+    /// Hash a model file
+    pub fn of_file(model_id: &str, path: &Path) -> Result<Self> {
+        Ok(Self::of_bytes(model_id, path, &read_model(path)?))
+    }
+
+    /// Hash the contents of a model file read from `path`
+    ///
+    /// Engines are loaded from the same bytes, so the measurement describes
+    /// exactly the weights that run.
+    pub fn of_bytes(model_id: &str, path: &Path, bytes: &[u8]) -> Self {
+        Self {
+            model_id: model_id.to_string(),
+            file: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            sha256: hex::encode(Sha256::digest(bytes)),
+            blake2b256: hex::encode(Blake2b256::digest(bytes).digest),
+            size_bytes: bytes.len() as u64,
+        }
+    }
+
+    /// Check the file against the digests it is expected to have, if any
+    pub fn verify(&self, sha256: Option<&str>, blake2b256: Option<&str>) -> Result<()> {
+        for (algorithm, expected, actual) in [
+            ("SHA-256", sha256, &self.sha256),
+            ("Blake2b-256", blake2b256, &self.blake2b256),
+        ] {
+            match expected {
+                Some(expected) if !expected.eq_ignore_ascii_case(actual) => {
+                    return Err(EnclaveError::IntegrityError(format!(
+                        "Model {} ({}) has {} {}, expected {}",
+                        self.model_id, self.file, algorithm, actual, expected
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails if `sha256` is not 32 hex-encoded bytes, e.g. in a tampered
+    /// `/get_measurements` response
+    fn leaf_hash(&self) -> Result<[u8; 32]> {
+        let mut file_hash = [0u8; 32];
+        hex::decode_to_slice(&self.sha256, &mut file_hash).map_err(|e| {
+            EnclaveError::IntegrityError(format!(
+                "Model {} has an invalid SHA-256 {:?}: {}",
+                self.model_id, self.sha256, e
+            ))
+        })?;
+        let mut hasher = Sha256::new();
+        hasher.update([0u8]);
+        hasher.update((self.model_id.len() as u32).to_be_bytes());
+        hasher.update(self.model_id.as_bytes());
+        hasher.update(file_hash);
+        Ok(hasher.finalize().into())
+    }
+}
+
+/// All loaded models and their Merkle root, as served by `/get_measurements`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelMeasurements {
+    /// Hex-encoded Merkle root, also found in attestation `user_data`
+    pub merkle_root: String,
+    /// Sorted by model id
+    pub models: Vec<ModelMeasurement>,
+}
+
+impl ModelMeasurements {
+    pub fn new(mut models: Vec<ModelMeasurement>) -> Result<Self> {
+        models.sort_by(|a, b| a.model_id.cmp(&b.model_id));
+        Ok(Self {
+            merkle_root: hex::encode(merkle_root(&models)?),
+            models,
+        })
+    }
+
+    /// Recompute the root from the listed hashes
+    pub fn root(&self) -> Result<[u8; 32]> {
+        merkle_root(&self.models)
+    }
+}
+
+/// Merkle root over measurements, which must be sorted by model id
+pub fn merkle_root(models: &[ModelMeasurement]) -> Result<[u8; 32]> {
+    let mut level = models
+        .iter()
+        .map(ModelMeasurement::leaf_hash)
+        .collect::<Result<Vec<_>>>()?;
+    if level.is_empty() {
+        return Ok(Sha256::digest([]).into());
+    }
+
+    while level.len() > 1 {
+        level = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Sha256::new();
+                    hasher.update([1u8]);
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [odd] => *odd,
+                _ => unreachable!("chunks(2) yields one or two nodes"),
+            })
+            .collect();
+    }
+    Ok(level[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn measurement(model_id: &str, contents: &[u8]) -> ModelMeasurement {
+        ModelMeasurement {
+            model_id: model_id.to_string(),
+            file: format!("{}.onnx", model_id),
+            sha256: hex::encode(Sha256::digest(contents)),
+            blake2b256: hex::encode(Blake2b256::digest(contents).digest),
+            size_bytes: contents.len() as u64,
+        }
+    }
+
+    #[test]
+    fn test_merkle_root_structure() {
+        let a = measurement("a", b"weights-a");
+        let b = measurement("b", b"weights-b");
+        let c = measurement("c", b"weights-c");
+
+        let node = |left: [u8; 32], right: [u8; 32]| -> [u8; 32] {
+            Sha256::digest([&[1u8][..], &left, &right].concat()).into()
+        };
+
+        let root = |models: &[ModelMeasurement]| merkle_root(models).unwrap();
+        let leaf = |model: &ModelMeasurement| model.leaf_hash().unwrap();
+        assert_eq!(root(&[]), <[u8; 32]>::from(Sha256::digest([])));
+        assert_eq!(root(std::slice::from_ref(&a)), leaf(&a));
+        assert_eq!(
+            root(&[a.clone(), b.clone(), c.clone()]),
+            node(node(leaf(&a), leaf(&b)), leaf(&c))
+        );
+
+        // Order of loading does not matter, the model id is bound
+        let sorted = ModelMeasurements::new(vec![c.clone(), a.clone(), b.clone()]).unwrap();
+        assert_eq!(sorted.root().unwrap(), root(&[a.clone(), b.clone(), c]));
+        let renamed = ModelMeasurement {
+            model_id: "z".to_string(),
+            ..a.clone()
+        };
+        assert_ne!(root(&[renamed]), root(std::slice::from_ref(&a)));
+
+        // A digest that is not 32 bytes of hex is not hashed as empty
+        for sha256 in ["", "zz", "ab"] {
+            let malformed = ModelMeasurement {
+                sha256: sha256.to_string(),
+                ..a.clone()
+            };
+            assert!(matches!(
+                merkle_root(&[malformed]),
+                Err(EnclaveError::IntegrityError(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_measure_file() {
+        let path = std::env::temp_dir().join(format!("measure-{}.onnx", std::process::id()));
+        std::fs::write(&path, b"model bytes").unwrap();
+        let measured = ModelMeasurement::of_file("m", &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(
+            measured,
+            ModelMeasurement {
+                file: path.file_name().unwrap().to_string_lossy().into_owned(),
+                ..measurement("m", b"model bytes")
+            }
+        );
+    }
+
+    #[test]
+    fn test_verify_digests() {
+        let measured = measurement("m", b"model bytes");
+        assert!(measured.verify(None, None).is_ok());
+        assert!(measured
+            .verify(
+                Some(&measured.sha256.to_uppercase()),
+                Some(&measured.blake2b256)
+            )
+            .is_ok());
+        // Sui hashes with Blake2b-256, e.g. `sui::hash::blake2b256`
+        assert_eq!(
+            measurement("m", b"").blake2b256,
+            "0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+
+        let other = measurement("m", b"other bytes");
+        for (sha256, blake2b256) in [
+            (Some(other.sha256.as_str()), None),
+            (None, Some(other.blake2b256.as_str())),
+            (
+                Some(measured.sha256.as_str()),
+                Some(other.blake2b256.as_str()),
+            ),
+        ] {
+            assert!(matches!(
+                measured.verify(sha256, blake2b256),
+                Err(EnclaveError::IntegrityError(_))
+            ));
+        }
+    }
+}
@@ -1,8 +1,34 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
+pub mod backend;
+pub mod cache;
+pub mod manifest;
+pub mod measurement;
+#[cfg(feature = "mock-inference")]
+pub mod mock_runtime;
 pub mod model_loader;
+#[cfg(feature = "onnx-ort")]
 pub mod onnx_runtime;
+pub mod schema;
+pub mod sui_registry;
+pub mod tensor;
+#[cfg(feature = "onnx-tract")]
+pub mod tract_runtime;
+pub mod walrus;
 
+pub use backend::*;
+pub use cache::*;
+pub use manifest::*;
+pub use measurement::*;
+#[cfg(feature = "mock-inference")]
+pub use mock_runtime::*;
 pub use model_loader::*;
+#[cfg(feature = "onnx-ort")]
 pub use onnx_runtime::*;
+pub use schema::*;
+pub use sui_registry::*;
+pub use tensor::*;
+#[cfg(feature = "onnx-tract")]
+pub use tract_runtime::*;
+pub use walrus::*;
@@ -1,88 +1,524 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
-use crate::models::ONNXModel;
+use crate::config::ModelSettings;
+use crate::models::{
+    read_model, BackendKind, LoadedModel, ModelCache, ModelEntry, ModelManifest, ModelMeasurement,
+    ModelMeasurements, ModelSchema, Postprocessing, Preprocessing, SuiRegistryClient,
+    SuiRegistryConfig, WalrusClient, WalrusConfig,
+};
 use crate::{EnclaveError, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use tracing::info;
 
+/// Models registered on Sui and stored on Walrus
+struct OnChainModels {
+    registry: SuiRegistryClient,
+    walrus: WalrusClient,
+}
+
 /// Model registry and loader
 pub struct ModelLoader {
     models_dir: PathBuf,
-    cache: RwLock<HashMap<String, ONNXModel>>,
+    /// Manifest entries by model id
+    entries: BTreeMap<String, ModelEntry>,
+    /// Source of models that are not in the manifest
+    onchain: Option<OnChainModels>,
+    /// On-chain models fetched so far, with the path of their blob
+    remote: RwLock<BTreeMap<String, (ModelEntry, PathBuf)>>,
+    /// Per-model engine overrides, taking precedence over the manifest
+    backends: HashMap<String, BackendKind>,
+    cache: ModelCache,
+    /// File hashes of the models in the cache
+    measurements: RwLock<BTreeMap<String, ModelMeasurement>>,
 }
 
+/// Models directory when none is configured
+pub const DEFAULT_MODELS_DIR: &str = "/app/models";
+
 impl ModelLoader {
-    /// Create new model loader
-    pub fn new() -> Self {
-        let models_dir = std::env::var("MODELS_DIR")
-            .unwrap_or_else(|_| "/app/models".to_string())
-            .into();
-        
+    /// Serve `settings.dir` with its cache budget and engine overrides and,
+    /// with a `registry_id`, resolve unlisted models on-chain
+    ///
+    /// Registry and Walrus requests go through `proxy` when one is given.
+    pub fn from_settings(settings: &ModelSettings, proxy: Option<&str>) -> Result<Self> {
+        let mut loader = Self::from_dir(&settings.dir)?;
+        if settings.cache_budget_bytes > 0 {
+            loader = loader.with_cache_budget(settings.cache_budget_bytes);
+        }
+        loader.backends.extend(settings.backends.clone());
+
+        let Some(registry_id) = &settings.registry_id else {
+            return Ok(loader);
+        };
+        let registry = SuiRegistryConfig {
+            proxy: proxy.map(String::from),
+            ..SuiRegistryConfig::new(&settings.sui_rpc_url, registry_id)
+        };
+        let walrus = WalrusConfig {
+            proxy: proxy.map(String::from),
+            ..WalrusConfig::new(
+                settings.walrus_aggregators.clone(),
+                &settings.walrus_cache_dir,
+            )
+        };
+        info!(
+            "Resolving unlisted models from registry {} via {}",
+            registry.registry_id, registry.rpc_url
+        );
+        Ok(loader.with_onchain_registry(
+            SuiRegistryClient::new(registry)?,
+            WalrusClient::new(walrus)?,
+        ))
+    }
+
+    /// Serve the models listed in the manifest in `models_dir`
+    pub fn from_dir(models_dir: impl Into<PathBuf>) -> Result<Self> {
+        let models_dir = models_dir.into();
+        let manifest = ModelManifest::load_dir(&models_dir)?;
+        Ok(Self::with_models_dir(models_dir).with_manifest(manifest))
+    }
+
+    /// Create a loader reading from `models_dir`, with no models listed yet
+    pub fn with_models_dir(models_dir: impl Into<PathBuf>) -> Self {
+        let models_dir = models_dir.into();
         info!("Model loader initialized with directory: {:?}", models_dir);
-        
+
         Self {
             models_dir,
-            cache: RwLock::new(HashMap::new()),
+            entries: BTreeMap::new(),
+            onchain: None,
+            remote: RwLock::new(BTreeMap::new()),
+            backends: HashMap::new(),
+            cache: ModelCache::default(),
+            measurements: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Serve the models listed in `manifest`
+    pub fn with_manifest(mut self, manifest: ModelManifest) -> Self {
+        self.entries.extend(
+            manifest
+                .models
+                .into_iter()
+                .map(|entry| (entry.id.clone(), entry)),
+        );
+        self
+    }
+
+    /// Resolve models missing from the manifest in `registry`, fetching
+    /// their blobs with `walrus`
+    pub fn with_onchain_registry(
+        mut self,
+        registry: SuiRegistryClient,
+        walrus: WalrusClient,
+    ) -> Self {
+        self.onchain = Some(OnChainModels { registry, walrus });
+        self
+    }
+
+    /// Evict least recently used models to keep loaded models under `budget_bytes`
+    pub fn with_cache_budget(mut self, budget_bytes: usize) -> Self {
+        self.cache = ModelCache::new(Some(budget_bytes));
+        self
+    }
+
+    /// Run `model_id` on `backend` instead of the engine in its manifest entry
+    pub fn with_backend(mut self, model_id: impl Into<String>, backend: BackendKind) -> Self {
+        self.backends.insert(model_id.into(), backend);
+        self
+    }
+
+    /// Engine that `model_id` is loaded with
+    pub fn backend_for(&self, model_id: &str) -> BackendKind {
+        self.backends
+            .get(model_id)
+            .copied()
+            .or_else(|| {
+                self.locate(model_id)
+                    .ok()
+                    .and_then(|(entry, _)| entry.backend)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Manifest entry of `model_id`, or the one made when it was resolved on-chain
+    pub fn entry(&self, model_id: &str) -> Result<ModelEntry> {
+        Ok(self.locate(model_id)?.0)
+    }
+
+    /// Entries of every manifest and resolved on-chain model, sorted by id
+    pub fn entries(&self) -> Vec<ModelEntry> {
+        let remote = self.remote.read().unwrap();
+        let mut entries: Vec<_> = self
+            .entries
+            .values()
+            .chain(remote.values().map(|(entry, _)| entry))
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| a.id.cmp(&b.id));
+        entries
+    }
+
+    /// Make `model_id` loadable, fetching it from Walrus if it is only
+    /// registered on-chain; its on-chain id is its blob id
+    ///
+    /// The registry holds no digest of the blob, so an on-chain model is only
+    /// fetched with the caller's `expected_sha256`, which the download must
+    /// match before the entry is registered.
+    pub async fn resolve(&self, model_id: &str, expected_sha256: Option<&str>) -> Result<()> {
+        if self.locate(model_id).is_ok() {
+            return Ok(());
         }
+        let Some(onchain) = &self.onchain else {
+            return Err(unknown_model(model_id));
+        };
+        let expected_sha256 = expected_sha256.ok_or_else(|| {
+            EnclaveError::ValidationError(format!(
+                "model_sha256 is required to fetch on-chain model {}",
+                model_id
+            ))
+        })?;
+
+        // Concurrent resolves of one model share the Walrus download, which
+        // is single-flighted per blob; other models are not held up
+        let registered = onchain.registry.resolve(model_id).await?;
+        let blob = onchain
+            .walrus
+            .fetch(&registered.blob_id, Some(expected_sha256))
+            .await?;
+        info!(
+            "Resolved on-chain model {} ({}) to {:?}",
+            model_id, registered.name, blob.path
+        );
+        let entry = ModelEntry {
+            id: model_id.to_string(),
+            file: registered.blob_id,
+            version: None,
+            backend: None,
+            // The digest the caller vouched for, rechecked on every load
+            sha256: Some(blob.sha256),
+            blake2b256: None,
+            pinned: false,
+            description: registered.description,
+            input: None,
+            preprocessing: Preprocessing::None,
+            postprocessing: Postprocessing::None,
+            labels: Vec::new(),
+        };
+        self.remote
+            .write()
+            .unwrap()
+            .entry(model_id.to_string())
+            .or_insert((entry, blob.path));
+        Ok(())
     }
-    
-    /// Load model by ID
-    pub fn load_model(&self, model_id: &str) -> Result<ONNXModel> {
-        // Check cache first
-        {
-            let cache = self.cache.read().unwrap();
-            if let Some(_model) = cache.get(model_id) {
-                info!("Model {} loaded from cache", model_id);
-                // Note: In production, return a clone or Arc reference
-                // For now, we'll reload
-            }
+
+    /// Load model by ID, or share the cached one
+    ///
+    /// The file is read once; the measurement is of the bytes the engine
+    /// parsed.
+    pub async fn load_model(&self, model_id: &str) -> Result<LoadedModel> {
+        let (entry, model_path) = self.locate(model_id)?;
+        let backend = self.backend_for(model_id);
+        let pinned = entry.pinned;
+        let loaded = self
+            .cache
+            .get_or_load(model_id, pinned, move || {
+                let bytes = read_model(&model_path)?;
+                // Check the bytes are the ones the manifest expects before parsing them
+                let measurement = ModelMeasurement::of_bytes(&entry.id, &model_path, &bytes);
+                measurement.verify(entry.sha256.as_deref(), entry.blake2b256.as_deref())?;
+                let model = backend.from_bytes(&model_path, &bytes)?;
+                Ok(LoadedModel {
+                    model: Arc::from(model),
+                    measurement,
+                })
+            })
+            .await?;
+
+        // Record the file hash for attestation, and drop those of the models
+        // no longer cached, by this load or a concurrent one
+        let mut measurements = self.measurements.write().unwrap();
+        measurements.insert(model_id.to_string(), loaded.measurement.clone());
+        for evicted_id in self.cache.take_evicted() {
+            measurements.remove(&evicted_id);
         }
-        
-        // Get model path
-        let model_path = self.get_model_path(model_id)?;
-        
-        // Load model
-        let model = ONNXModel::load(&model_path)?;
-        
-        // Cache model
-        {
-            let mut cache = self.cache.write().unwrap();
-            cache.insert(model_id.to_string(), model);
+        drop(measurements);
+        Ok(loaded)
+    }
+
+    /// Load `model_id`, checked against the digests a request expects on
+    /// top of those in the manifest
+    pub async fn load_verified(
+        &self,
+        model_id: &str,
+        sha256: Option<&str>,
+        blake2b256: Option<&str>,
+    ) -> Result<LoadedModel> {
+        let loaded = self.load_model(model_id).await?;
+        loaded.measurement.verify(sha256, blake2b256)?;
+        Ok(loaded)
+    }
+
+    /// Models currently held in memory
+    pub fn cache(&self) -> &ModelCache {
+        &self.cache
+    }
+
+    /// Entry and file path of a manifest or resolved on-chain model
+    fn locate(&self, model_id: &str) -> Result<(ModelEntry, PathBuf)> {
+        if let Some(entry) = self.entries.get(model_id) {
+            return Ok((entry.clone(), self.models_dir.join(&entry.file)));
         }
-        
-        // Return model (in production, return from cache)
-        ONNXModel::load(&model_path)
+        self.remote
+            .read()
+            .unwrap()
+            .get(model_id)
+            .cloned()
+            .ok_or_else(|| unknown_model(model_id))
     }
-    
+
     /// Get model file path
     fn get_model_path(&self, model_id: &str) -> Result<PathBuf> {
-        let path = match model_id {
-            "mnist-classifier" => self.models_dir.join("mnist.onnx"),
-            "sentiment-analysis" => self.models_dir.join("sentiment.onnx"),
-            _ => {
-                return Err(EnclaveError::ModelNotFound(
-                    format!("Unknown model ID: {}", model_id)
-                ))
-            }
-        };
-        
-        Ok(path)
+        Ok(self.locate(model_id)?.1)
     }
-    
+
     /// List available models
     pub fn list_models(&self) -> Vec<String> {
-        vec![
-            "mnist-classifier".to_string(),
-            "sentiment-analysis".to_string(),
-        ]
+        self.entries().into_iter().map(|entry| entry.id).collect()
+    }
+
+    /// Input/output schema of `model_id`, read without loading the model
+    pub fn schema(&self, model_id: &str) -> Result<ModelSchema> {
+        ModelSchema::of_file(model_id, &self.get_model_path(model_id)?)
+    }
+
+    /// Hashes and Merkle root of the models currently cached
+    pub fn measurements(&self) -> Result<ModelMeasurements> {
+        ModelMeasurements::new(
+            self.measurements
+                .read()
+                .unwrap()
+                .values()
+                .cloned()
+                .collect(),
+        )
     }
 }
 
-impl Default for ModelLoader {
-    fn default() -> Self {
-        Self::new()
+fn unknown_model(model_id: &str) -> EnclaveError {
+    EnclaveError::ModelNotFound(format!("Unknown model ID: {}", model_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::Digest;
+
+    fn models_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("model-loader-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_models_come_from_manifest() {
+        let dir = models_dir("manifest");
+        std::fs::write(
+            dir.join("models.toml"),
+            "[[model]]\nid = \"digits\"\nfile = \"digits-v2.onnx\"\nbackend = \"tract\"\n",
+        )
+        .unwrap();
+        let loader = ModelLoader::from_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loader.list_models(), vec!["digits".to_string()]);
+        assert_eq!(
+            loader.get_model_path("digits").unwrap(),
+            dir.join("digits-v2.onnx")
+        );
+        assert_eq!(loader.backend_for("digits"), BackendKind::Tract);
+        let loader = loader.with_backend("digits", BackendKind::Ort);
+        assert_eq!(loader.backend_for("digits"), BackendKind::Ort);
+        assert!(matches!(
+            loader.load_model("mnist-classifier").await,
+            Err(EnclaveError::ModelNotFound(_))
+        ));
+
+        // No manifest, no models
+        assert!(ModelLoader::from_dir(&dir)
+            .unwrap()
+            .list_models()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tampered_model_is_not_loaded() {
+        let dir = models_dir("tampered");
+        std::fs::write(dir.join("m.onnx"), b"tampered weights").unwrap();
+        let manifest = ModelManifest::from_toml_str(&format!(
+            "[[model]]\nid = \"m\"\nfile = \"m.onnx\"\nsha256 = \"{}\"\n",
+            hex::encode(sha2::Sha256::digest(b"uploaded weights"))
+        ))
+        .unwrap();
+        let loader = ModelLoader::with_models_dir(&dir).with_manifest(manifest);
+
+        let loaded = loader.load_model("m").await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(loaded, Err(EnclaveError::IntegrityError(_))));
+        assert!(loader.measurements().unwrap().models.is_empty());
+        assert!(loader.cache().cached_models().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_onchain_model() {
+        use crate::models::sui_registry::tests::{registered, spawn_rpc, REGISTRY_ID};
+        use crate::models::walrus::tests::{blob, spawn_aggregator, BLOB_ID};
+
+        let dir = models_dir("onchain");
+        let (aggregator, _) = spawn_aggregator(0).await;
+        let registry = SuiRegistryClient::new(SuiRegistryConfig::new(
+            spawn_rpc(vec![registered(BLOB_ID, "digits")]).await,
+            REGISTRY_ID,
+        ))
+        .unwrap();
+        let walrus =
+            WalrusClient::new(WalrusConfig::new(vec![aggregator], dir.join("walrus"))).unwrap();
+        let loader = ModelLoader::with_models_dir(&dir).with_onchain_registry(registry, walrus);
+
+        assert!(loader.entry(BLOB_ID).is_err());
+        assert!(matches!(
+            loader.resolve(BLOB_ID, None).await,
+            Err(EnclaveError::ValidationError(_))
+        ));
+        assert!(matches!(
+            loader.resolve(BLOB_ID, Some(&"00".repeat(32))).await,
+            Err(EnclaveError::IntegrityError(_))
+        ));
+        assert!(loader.entry(BLOB_ID).is_err());
+
+        let sha256 = hex::encode(sha2::Sha256::digest(blob()));
+        loader.resolve(BLOB_ID, Some(&sha256)).await.unwrap();
+        let entry = loader.entry(BLOB_ID).unwrap();
+        assert_eq!(entry.description, "digits on Walrus");
+        assert_eq!(entry.sha256.as_deref(), Some(sha256.as_str()));
+        assert_eq!(
+            std::fs::read(loader.get_model_path(BLOB_ID).unwrap()).unwrap(),
+            blob()
+        );
+        assert_eq!(loader.list_models(), vec![BLOB_ID.to_string()]);
+
+        assert!(matches!(
+            loader.resolve("unregistered", Some(&sha256)).await,
+            Err(EnclaveError::ModelNotFound(_))
+        ));
+
+        // Without a digest the fullnode is not even asked; nothing listens on port 1
+        let offline = ModelLoader::with_models_dir(&dir).with_onchain_registry(
+            SuiRegistryClient::new(SuiRegistryConfig::new("http://127.0.0.1:1", REGISTRY_ID))
+                .unwrap(),
+            WalrusClient::new(WalrusConfig::new(
+                vec!["http://127.0.0.1:1".to_string()],
+                dir.join("walrus"),
+            ))
+            .unwrap(),
+        );
+        assert!(matches!(
+            offline.resolve(BLOB_ID, None).await,
+            Err(EnclaveError::ValidationError(_))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Placeholder bytes are only accepted by the mock engine
+    #[cfg(feature = "mock-inference")]
+    #[tokio::test]
+    async fn test_loaded_models_are_measured() {
+        let dir = models_dir("measured");
+        std::fs::write(dir.join("mnist.onnx"), b"mnist weights").unwrap();
+        let manifest = ModelManifest::from_toml_str(
+            "[[model]]\nid = \"mnist-classifier\"\nfile = \"mnist.onnx\"\nbackend = \"mock\"\n\n\
+             [[model]]\nid = \"sentiment-analysis\"\nfile = \"sentiment.onnx\"\n",
+        )
+        .unwrap();
+        let loader = ModelLoader::with_models_dir(&dir).with_manifest(manifest);
+        assert_eq!(loader.backend_for("mnist-classifier"), BackendKind::Mock);
+
+        let empty_root = loader.measurements().unwrap().merkle_root;
+        let loaded = loader.load_model("mnist-classifier").await.unwrap();
+        assert_eq!(loaded.model.kind(), BackendKind::Mock);
+        let cached = loader.load_model("mnist-classifier").await.unwrap();
+        assert!(Arc::ptr_eq(&loaded.model, &cached.model));
+        assert!(loader.load_model("sentiment-analysis").await.is_err());
+
+        // The cached model keeps the measurement of the bytes it was built from
+        std::fs::write(dir.join("mnist.onnx"), b"swapped weights").unwrap();
+        let sha256 = hex::encode(sha2::Sha256::digest(b"mnist weights"));
+        assert_eq!(
+            loader
+                .load_model("mnist-classifier")
+                .await
+                .unwrap()
+                .measurement
+                .sha256,
+            sha256
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let verified = loader
+            .load_verified("mnist-classifier", Some(&sha256), None)
+            .await
+            .unwrap();
+        assert_eq!(verified.measurement.sha256, sha256);
+        assert!(Arc::ptr_eq(&verified.model, &loaded.model));
+        assert!(matches!(
+            loader
+                .load_verified("mnist-classifier", Some(&"0".repeat(64)), None)
+                .await,
+            Err(EnclaveError::IntegrityError(_))
+        ));
+
+        let measurements = loader.measurements().unwrap();
+        assert_eq!(measurements.models.len(), 1);
+        assert_eq!(measurements.models[0].file, "mnist.onnx");
+        assert_ne!(measurements.merkle_root, empty_root);
+    }
+
+    #[cfg(feature = "onnx-tract")]
+    #[tokio::test]
+    async fn test_evicted_models_leave_the_measurements() {
+        let dir = models_dir("evicted");
+        let fixture =
+            PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/models/tiny_mlp.onnx");
+        for file in ["a.onnx", "b.onnx"] {
+            std::fs::copy(&fixture, dir.join(file)).unwrap();
+        }
+        let manifest = ModelManifest::from_toml_str(
+            "[[model]]\nid = \"a\"\nfile = \"a.onnx\"\nbackend = \"tract\"\n\n\
+             [[model]]\nid = \"b\"\nfile = \"b.onnx\"\nbackend = \"tract\"\n",
+        )
+        .unwrap();
+        // Room for one model at a time
+        let size_bytes = std::fs::metadata(&fixture).unwrap().len() as usize;
+        let loader = ModelLoader::with_models_dir(&dir)
+            .with_manifest(manifest)
+            .with_cache_budget(size_bytes);
+
+        loader.load_model("a").await.unwrap();
+        loader.load_model("b").await.unwrap();
+        assert_eq!(loader.cache().cached_models(), ["b"]);
+        let measurements = loader.measurements().unwrap();
+        assert_eq!(measurements.models.len(), 1);
+        assert_eq!(measurements.models[0].file, "b.onnx");
+
+        loader.load_model("a").await.unwrap();
+        let measurements = loader.measurements().unwrap();
+        assert_eq!(measurements.models.len(), 1);
+        assert_eq!(measurements.models[0].file, "a.onnx");
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -1,86 +1,176 @@
 // Copyright (c) 2025, SynapseModel Team
 // SPDX-License-Identifier: Apache-2.0
 
+//! ONNX Runtime inference engine
+//!
+//! Runs the graph on CPU through the ONNX Runtime shared library, which is
+//! loaded at runtime from `ORT_DYLIB_PATH`.
+
+use crate::models::{bind_inputs, BackendKind, InferenceBackend, Tensor, TensorData, TensorInfo};
 use crate::{EnclaveError, Result};
+use ort::session::builder::GraphOptimizationLevel;
+use ort::session::Session;
+use ort::tensor::{PrimitiveTensorElementType, TensorElementType};
+use ort::value::{DynValue, Value, ValueType};
 use std::path::Path;
-use tracing::{info, debug};
+use std::sync::Mutex;
+use tracing::{debug, info};
 
-/// ONNX Runtime model wrapper
-pub struct ONNXModel {
+/// ONNX Runtime session for one model
+pub struct OrtBackend {
     model_path: String,
-    // In production, this would hold the actual ONNX session
-    // session: ort::Session,
+    session: Mutex<Session>,
+    inputs: Vec<TensorInfo>,
+    outputs: Vec<TensorInfo>,
+    size_bytes: usize,
 }
 
-impl ONNXModel {
-    /// Load ONNX model from file
-    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
-        let model_path = path.as_ref().to_string_lossy().to_string();
-        
-        info!("Loading ONNX model from: {}", model_path);
-        
-        // In production with actual ONNX Runtime:
-        // let environment = Arc::new(Environment::builder().build()?);
-        // let session = SessionBuilder::new(&environment)?
-        //     .with_model_from_file(&model_path)?;
-        
-        // For now, just verify file exists
-        if !path.as_ref().exists() {
-            return Err(EnclaveError::ModelNotFound(
-                format!("Model file not found: {}", model_path)
-            ));
-        }
-        
-        Ok(Self { model_path })
+impl InferenceBackend for OrtBackend {
+    fn from_bytes(path: &Path, bytes: &[u8]) -> Result<Self> {
+        let model_path = path.to_string_lossy().to_string();
+        info!("Loading ONNX model into ONNX Runtime from: {}", model_path);
+
+        let session = Session::builder()
+            .and_then(|builder| builder.with_optimization_level(GraphOptimizationLevel::Level3))
+            .and_then(|builder| builder.commit_from_memory(bytes))
+            .map_err(|e| {
+                EnclaveError::InferenceError(format!(
+                    "Failed to load {} into ONNX Runtime: {}",
+                    model_path, e
+                ))
+            })?;
+
+        let inputs = session
+            .inputs
+            .iter()
+            .map(|input| tensor_info(&input.name, &input.input_type))
+            .collect();
+        let outputs = session
+            .outputs
+            .iter()
+            .map(|output| tensor_info(&output.name, &output.output_type))
+            .collect();
+
+        Ok(Self {
+            model_path,
+            session: Mutex::new(session),
+            inputs,
+            outputs,
+            // The weights are kept in memory
+            size_bytes: bytes.len(),
+        })
     }
-    
-    /// Run inference
-    pub fn run_inference(&self, input: &[f32]) -> Result<Vec<f32>> {
-        debug!("Running inference on {} input values", input.len());
-        
-        // In production with actual ONNX Runtime:
-        // let input_tensor = Array::from_shape_vec((1, 1, 28, 28), input.to_vec())?;
-        // let outputs = self.session.run(vec![input_tensor])?;
-        // let output: ArrayView<f32, _> = outputs[0].try_extract()?;
-        // return Ok(output.to_vec());
-        
-        // Placeholder: Return dummy softmax output for 10 classes
-        let dummy_output = self.generate_dummy_output(input);
-        Ok(dummy_output)
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::Ort
+    }
+
+    fn inputs(&self) -> &[TensorInfo] {
+        &self.inputs
     }
-    
-    /// Generate dummy output for testing
-    fn generate_dummy_output(&self, input: &[f32]) -> Vec<f32> {
-        // Simple heuristic based on input
-        let sum: f32 = input.iter().sum();
-        let avg = sum / input.len() as f32;
-        
-        // Generate 10 class probabilities
-        let mut probs = vec![0.05; 10];
-        let dominant_class = (avg * 10.0) as usize % 10;
-        probs[dominant_class] = 0.75;
-        
-        // Normalize
-        let total: f32 = probs.iter().sum();
-        probs.iter().map(|p| p / total).collect()
+
+    fn outputs(&self) -> &[TensorInfo] {
+        &self.outputs
+    }
+
+    fn run(&self, inputs: &[Tensor]) -> Result<Vec<Tensor>> {
+        debug!(
+            "Running inference with {} on {} inputs",
+            self.model_path,
+            inputs.len()
+        );
+
+        let values = bind_inputs(&self.inputs, inputs)?
+            .into_iter()
+            .zip(&self.inputs)
+            .map(|(tensor, info)| Ok((info.name.clone(), to_ort_value(tensor)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut session = self.session.lock().unwrap();
+        let outputs = session.run(values).map_err(ort_err)?;
+        outputs
+            .iter()
+            .map(|(name, value)| from_ort_value(name, &value))
+            .collect()
+    }
+
+    fn memory_footprint(&self) -> usize {
+        self.size_bytes
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    #[test]
-    fn test_dummy_output() {
-        let model = ONNXModel {
-            model_path: "test".to_string(),
-        };
-        
-        let input = vec![0.5; 784];
-        let output = model.generate_dummy_output(&input);
-        
-        assert_eq!(output.len(), 10);
-        let sum: f32 = output.iter().sum();
-        assert!((sum - 1.0).abs() < 0.01); // Should sum to 1.0
+/// Non-tensor values (sequences, maps) are listed without a shape
+fn tensor_info(name: &str, value_type: &ValueType) -> TensorInfo {
+    let shape = match value_type {
+        ValueType::Tensor { shape, .. } => shape.to_vec(),
+        _ => Vec::new(),
+    };
+    TensorInfo {
+        name: name.to_string(),
+        shape,
     }
 }
+
+fn ort_err(e: ort::Error) -> EnclaveError {
+    EnclaveError::InferenceError(format!("ONNX Runtime: {}", e))
+}
+
+fn to_ort_value(tensor: &Tensor) -> Result<DynValue> {
+    let shape = tensor.shape().to_vec();
+    match tensor.data() {
+        TensorData::F32(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::F16(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::I32(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::I64(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::U8(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::Bool(data) => {
+            ort::value::Tensor::from_array((shape, data.clone())).map(Value::into_dyn)
+        }
+        TensorData::String(data) => {
+            ort::value::Tensor::from_string_array((shape, data.as_slice())).map(Value::into_dyn)
+        }
+    }
+    .map_err(ort_err)
+}
+
+fn from_ort_value(name: &str, value: &DynValue) -> Result<Tensor> {
+    let ValueType::Tensor { ty, shape, .. } = value.dtype() else {
+        return Err(EnclaveError::InferenceError(format!(
+            "Output {} is not a tensor",
+            name
+        )));
+    };
+    let shape = shape.iter().map(|&dim| dim as usize).collect();
+    let data = match ty {
+        TensorElementType::Float32 => TensorData::F32(extract(value)?),
+        TensorElementType::Float16 => TensorData::F16(extract(value)?),
+        TensorElementType::Int32 => TensorData::I32(extract(value)?),
+        TensorElementType::Int64 => TensorData::I64(extract(value)?),
+        TensorElementType::Uint8 => TensorData::U8(extract(value)?),
+        TensorElementType::Bool => TensorData::Bool(extract(value)?),
+        TensorElementType::String => {
+            TensorData::String(value.try_extract_strings().map_err(ort_err)?.1)
+        }
+        other => {
+            return Err(EnclaveError::InferenceError(format!(
+                "Output {} has unsupported element type {}",
+                name, other
+            )))
+        }
+    };
+    Tensor::new(name, shape, data)
+}
+
+fn extract<T: PrimitiveTensorElementType + Clone>(value: &DynValue) -> Result<Vec<T>> {
+    Ok(value.try_extract_tensor::<T>().map_err(ort_err)?.1.to_vec())
+}
@@ -0,0 +1,320 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Input/output schema read straight from an `.onnx` file
+//!
+//! Decodes just the `ModelProto` fields clients need to build requests,
+//! independent of the inference engine, so `/models/{id}/schema` works
+//! without loading the model. Element types use the same names as
+//! [`DType`](crate::models::DType); types the JSON tensors cannot carry keep
+//! their ONNX name in lowercase (e.g. `f64`, `bf16`).
+
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Graph metadata and IO of one model, as served by `/models/{id}/schema`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelSchema {
+    pub model_id: String,
+    pub ir_version: i64,
+    pub producer_name: String,
+    pub producer_version: String,
+    pub opset_import: Vec<OpsetImport>,
+    pub inputs: Vec<TensorSchema>,
+    pub outputs: Vec<TensorSchema>,
+}
+
+/// Operator set the graph was built against; `""` is the default ONNX domain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpsetImport {
+    pub domain: String,
+    pub version: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorSchema {
+    pub name: String,
+    pub dtype: String,
+    pub shape: Vec<Dim>,
+}
+
+/// One dimension: a size, a symbol such as `"batch"`, or `null` if unnamed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Dim {
+    Fixed(i64),
+    Symbolic(String),
+    Unknown,
+}
+
+impl ModelSchema {
+    /// Read the schema of the model file at `path`
+    pub fn of_file(model_id: &str, path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| {
+            EnclaveError::ModelNotFound(format!("Failed to read {:?}: {}", path, e))
+        })?;
+        Self::decode(model_id, &bytes).map_err(|e| {
+            EnclaveError::InferenceError(format!("Malformed ONNX model {:?}: {}", path, e))
+        })
+    }
+
+    /// Decode a serialized `ModelProto`
+    fn decode(model_id: &str, bytes: &[u8]) -> std::result::Result<Self, String> {
+        let mut schema = ModelSchema {
+            model_id: model_id.to_string(),
+            ir_version: 0,
+            producer_name: String::new(),
+            producer_version: String::new(),
+            opset_import: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+        };
+        let mut graph = None;
+
+        let mut fields = Fields(bytes);
+        while let Some((number, value)) = fields.next()? {
+            match (number, value) {
+                (1, Value::Varint(v)) => schema.ir_version = v as i64,
+                (2, Value::Bytes(b)) => schema.producer_name = string(b)?,
+                (3, Value::Bytes(b)) => schema.producer_version = string(b)?,
+                (7, Value::Bytes(b)) => graph = Some(b),
+                (8, Value::Bytes(b)) => schema.opset_import.push(opset_import(b)?),
+                _ => {}
+            }
+        }
+
+        let graph = graph.ok_or("model has no graph")?;
+        let mut initializers = Vec::new();
+        let mut fields = Fields(graph);
+        while let Some((number, value)) = fields.next()? {
+            match (number, value) {
+                (5, Value::Bytes(b)) => initializers.push(initializer_name(b)?),
+                (11, Value::Bytes(b)) => schema.inputs.push(value_info(b)?),
+                (12, Value::Bytes(b)) => schema.outputs.push(value_info(b)?),
+                _ => {}
+            }
+        }
+        // Before IR 4 weights were also listed as graph inputs
+        schema
+            .inputs
+            .retain(|input| !initializers.contains(&input.name));
+
+        Ok(schema)
+    }
+}
+
+/// `OperatorSetIdProto`
+fn opset_import(bytes: &[u8]) -> std::result::Result<OpsetImport, String> {
+    let mut opset = OpsetImport {
+        domain: String::new(),
+        version: 0,
+    };
+    let mut fields = Fields(bytes);
+    while let Some((number, value)) = fields.next()? {
+        match (number, value) {
+            (1, Value::Bytes(b)) => opset.domain = string(b)?,
+            (2, Value::Varint(v)) => opset.version = v as i64,
+            _ => {}
+        }
+    }
+    Ok(opset)
+}
+
+/// Name of a `TensorProto`, skipping its data
+fn initializer_name(bytes: &[u8]) -> std::result::Result<String, String> {
+    let mut name = String::new();
+    let mut fields = Fields(bytes);
+    while let Some((number, value)) = fields.next()? {
+        if let (8, Value::Bytes(b)) = (number, value) {
+            name = string(b)?;
+        }
+    }
+    Ok(name)
+}
+
+/// `ValueInfoProto` -> `TypeProto` -> `TypeProto.Tensor` -> `TensorShapeProto`
+fn value_info(bytes: &[u8]) -> std::result::Result<TensorSchema, String> {
+    let mut tensor = TensorSchema {
+        name: String::new(),
+        dtype: "unknown".to_string(),
+        shape: Vec::new(),
+    };
+    let mut fields = Fields(bytes);
+    while let Some((number, value)) = fields.next()? {
+        match (number, value) {
+            (1, Value::Bytes(b)) => tensor.name = string(b)?,
+            (2, Value::Bytes(type_proto)) => {
+                let mut fields = Fields(type_proto);
+                while let Some((number, value)) = fields.next()? {
+                    if let (1, Value::Bytes(tensor_type)) = (number, value) {
+                        tensor_type_into(tensor_type, &mut tensor)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(tensor)
+}
+
+fn tensor_type_into(bytes: &[u8], tensor: &mut TensorSchema) -> std::result::Result<(), String> {
+    let mut fields = Fields(bytes);
+    while let Some((number, value)) = fields.next()? {
+        match (number, value) {
+            (1, Value::Varint(elem_type)) => tensor.dtype = dtype_name(elem_type).to_string(),
+            (2, Value::Bytes(shape)) => {
+                let mut dims = Fields(shape);
+                while let Some((number, value)) = dims.next()? {
+                    if let (1, Value::Bytes(dim)) = (number, value) {
+                        tensor.shape.push(dimension(dim)?);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `TensorShapeProto.Dimension`
+fn dimension(bytes: &[u8]) -> std::result::Result<Dim, String> {
+    let mut dim = Dim::Unknown;
+    let mut fields = Fields(bytes);
+    while let Some((number, value)) = fields.next()? {
+        match (number, value) {
+            (1, Value::Varint(v)) => dim = Dim::Fixed(v as i64),
+            (2, Value::Bytes(b)) => dim = Dim::Symbolic(string(b)?),
+            _ => {}
+        }
+    }
+    Ok(dim)
+}
+
+/// `TensorProto.DataType`
+fn dtype_name(elem_type: u64) -> &'static str {
+    match elem_type {
+        1 => "f32",
+        2 => "u8",
+        3 => "i8",
+        4 => "u16",
+        5 => "i16",
+        6 => "i32",
+        7 => "i64",
+        8 => "string",
+        9 => "bool",
+        10 => "f16",
+        11 => "f64",
+        12 => "u32",
+        13 => "u64",
+        14 => "complex64",
+        15 => "complex128",
+        16 => "bf16",
+        _ => "unknown",
+    }
+}
+
+fn string(bytes: &[u8]) -> std::result::Result<String, String> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| "string field is not UTF-8".to_string())
+}
+
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+/// Protobuf wire-format fields of one message
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn next(&mut self) -> std::result::Result<Option<(u64, Value<'a>)>, String> {
+        if self.0.is_empty() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let value = match key & 7 {
+            0 => Value::Varint(self.varint()?),
+            1 => self.skip(8)?,
+            2 => {
+                let len = self.varint()? as usize;
+                let bytes = self
+                    .0
+                    .get(..len)
+                    .ok_or("length-delimited field is truncated")?;
+                self.0 = &self.0[len..];
+                Value::Bytes(bytes)
+            }
+            5 => self.skip(4)?,
+            wire_type => return Err(format!("unsupported wire type {}", wire_type)),
+        };
+        Ok(Some((key >> 3, value)))
+    }
+
+    fn varint(&mut self) -> std::result::Result<u64, String> {
+        let mut value = 0u64;
+        for (i, &byte) in self.0.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7f) << (7 * i);
+            if byte & 0x80 == 0 {
+                self.0 = &self.0[i + 1..];
+                return Ok(value);
+            }
+        }
+        Err("varint is truncated".to_string())
+    }
+
+    fn skip(&mut self, len: usize) -> std::result::Result<Value<'a>, String> {
+        if self.0.len() < len {
+            return Err("fixed-width field is truncated".to_string());
+        }
+        self.0 = &self.0[len..];
+        Ok(Value::Fixed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tiny_mlp_schema() {
+        let path =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/models/tiny_mlp.onnx");
+        let schema = ModelSchema::of_file("tiny-mlp", &path).unwrap();
+
+        assert_eq!(schema.model_id, "tiny-mlp");
+        assert_eq!(schema.ir_version, 8);
+        assert_eq!(schema.producer_name, "synapsemodel-fixtures");
+        assert_eq!(
+            schema.opset_import,
+            vec![OpsetImport {
+                domain: String::new(),
+                version: 13,
+            }]
+        );
+        assert_eq!(
+            schema.inputs,
+            vec![TensorSchema {
+                name: "input".to_string(),
+                dtype: "f32".to_string(),
+                shape: vec![Dim::Symbolic("N".to_string()), Dim::Fixed(4)],
+            }]
+        );
+        assert_eq!(schema.outputs[0].name, "probabilities");
+
+        assert_eq!(
+            serde_json::to_value(&schema.inputs[0]).unwrap(),
+            serde_json::json!({"name": "input", "dtype": "f32", "shape": ["N", 4]})
+        );
+    }
+
+    #[test]
+    fn test_malformed_model() {
+        assert!(ModelSchema::decode("m", b"not a model").is_err());
+        assert!(ModelSchema::decode("m", &[0x3a, 0x05, 0x00]).is_err());
+        assert_eq!(
+            ModelSchema::decode("m", &[]).unwrap_err(),
+            "model has no graph"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Reader for the on-chain `model_registry::registry::ModelRegistry`
+//!
+//! The shared registry object keeps a `Table<String, Model>` keyed by
+//! Walrus blob id plus the list of keys, so a model's on-chain id is its
+//! blob id. Both are read over Sui JSON-RPC: `sui_getObject` for the
+//! registry and `suix_getDynamicFieldObject` for each table entry.
+
+use crate::{EnclaveError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::debug;
+
+/// Model record stored in the registry table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisteredModel {
+    pub blob_id: String,
+    /// Walrus object id returned at upload
+    pub object_id: String,
+    pub name: String,
+    pub description: String,
+    /// Sui address of the uploader
+    pub uploader: String,
+    /// Milliseconds since the Unix epoch
+    pub uploaded_at: u64,
+}
+
+/// Fullnode and registry to read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SuiRegistryConfig {
+    pub rpc_url: String,
+    /// Object id of the shared `ModelRegistry`
+    pub registry_id: String,
+    /// HTTP proxy for RPC calls, e.g. the enclave's egress relay
+    pub proxy: Option<String>,
+    /// Per-request timeout, including the body
+    pub request_timeout: Duration,
+}
+
+impl SuiRegistryConfig {
+    pub fn new(rpc_url: impl Into<String>, registry_id: impl Into<String>) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            registry_id: registry_id.into(),
+            proxy: None,
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Sui JSON-RPC client for the model registry
+pub struct SuiRegistryClient {
+    config: SuiRegistryConfig,
+    http: reqwest::Client,
+}
+
+impl SuiRegistryClient {
+    pub fn new(config: SuiRegistryConfig) -> Result<Self> {
+        let mut http = reqwest::Client::builder().timeout(config.request_timeout);
+        if let Some(proxy) = &config.proxy {
+            http = http.proxy(reqwest::Proxy::all(proxy).map_err(|e| {
+                EnclaveError::ValidationError(format!("Invalid proxy {:?}: {}", proxy, e))
+            })?);
+        }
+        let http = http
+            .build()
+            .map_err(|e| EnclaveError::GenericError(format!("Sui RPC client: {}", e)))?;
+        Ok(Self { config, http })
+    }
+
+    pub fn config(&self) -> &SuiRegistryConfig {
+        &self.config
+    }
+
+    /// Every registered model, in upload order
+    pub async fn list_models(&self) -> Result<Vec<RegisteredModel>> {
+        let registry = self.registry_fields().await?;
+        let blob_ids = registry
+            .get("blob_ids")
+            .and_then(Value::as_array)
+            .ok_or_else(|| malformed("ModelRegistry has no blob_ids"))?;
+
+        let mut models = Vec::with_capacity(blob_ids.len());
+        for blob_id in blob_ids {
+            let blob_id = blob_id
+                .as_str()
+                .ok_or_else(|| malformed("blob id is not a string"))?;
+            models.push(self.table_entry(&registry, blob_id).await?);
+        }
+        Ok(models)
+    }
+
+    /// Registry record of `model_id`, which is its Walrus blob id
+    pub async fn resolve(&self, model_id: &str) -> Result<RegisteredModel> {
+        let registry = self.registry_fields().await?;
+        self.table_entry(&registry, model_id).await
+    }
+
+    /// Move fields of the registry object
+    async fn registry_fields(&self) -> Result<Value> {
+        let object = self
+            .call(
+                "sui_getObject",
+                json!([self.config.registry_id, {"showContent": true}]),
+            )
+            .await?;
+        if let Some(error) = object.get("error") {
+            return Err(EnclaveError::GenericError(format!(
+                "ModelRegistry {} not readable: {}",
+                self.config.registry_id, error
+            )));
+        }
+        object
+            .pointer("/data/content/fields")
+            .cloned()
+            .ok_or_else(|| malformed("ModelRegistry object has no content"))
+    }
+
+    async fn table_entry(&self, registry: &Value, blob_id: &str) -> Result<RegisteredModel> {
+        let table_id = registry
+            .pointer("/models/fields/id/id")
+            .and_then(Value::as_str)
+            .ok_or_else(|| malformed("ModelRegistry has no models table"))?;
+        let field = self
+            .call(
+                "suix_getDynamicFieldObject",
+                json!([table_id, {"type": "0x1::string::String", "value": blob_id}]),
+            )
+            .await?;
+        if field.get("error").is_some() {
+            return Err(EnclaveError::ModelNotFound(format!(
+                "Model {} is not in the on-chain registry",
+                blob_id
+            )));
+        }
+        let model = field
+            .pointer("/data/content/fields/value/fields")
+            .ok_or_else(|| malformed("registry entry has no Model value"))?;
+        parse_model(model)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        debug!("Sui RPC {} {}", method, params);
+        let rpc_err =
+            |e: reqwest::Error| EnclaveError::GenericError(format!("Sui RPC {}: {}", method, e));
+        let response: Value = self
+            .http
+            .post(&self.config.rpc_url)
+            .json(&json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params}))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(rpc_err)?
+            .json()
+            .await
+            .map_err(rpc_err)?;
+
+        if let Some(error) = response.get("error") {
+            return Err(EnclaveError::GenericError(format!(
+                "Sui RPC {} failed: {}",
+                method, error
+            )));
+        }
+        response
+            .get("result")
+            .cloned()
+            .ok_or_else(|| malformed("JSON-RPC response has no result"))
+    }
+}
+
+/// `Model` fields as the fullnode renders them; `u64`s arrive as strings
+fn parse_model(fields: &Value) -> Result<RegisteredModel> {
+    let string = |name: &str| {
+        fields
+            .get(name)
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| malformed(&format!("Model has no {}", name)))
+    };
+    let uploaded_at = match fields.get("uploaded_at") {
+        Some(Value::String(ms)) => ms.parse().ok(),
+        Some(Value::Number(ms)) => ms.as_u64(),
+        _ => None,
+    }
+    .ok_or_else(|| malformed("Model has no uploaded_at"))?;
+
+    Ok(RegisteredModel {
+        blob_id: string("blob_id")?,
+        object_id: string("object_id")?,
+        name: string("name")?,
+        description: string("description")?,
+        uploader: string("uploader")?,
+        uploaded_at,
+    })
+}
+
+fn malformed(reason: &str) -> EnclaveError {
+    EnclaveError::SerializationError(format!("Unexpected Sui RPC response: {}", reason))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::extract::State;
+    use axum::Json;
+    use std::sync::Arc;
+
+    pub(crate) const REGISTRY_ID: &str =
+        "0x5e5b3b0b4d1e4b3f6f0c2a9f1e8d7c6b5a4f3e2d1c0b9a8f7e6d5c4b3a291807";
+    const TABLE_ID: &str = "0x9a1f3c5e7d9b2a4c6e8f0a1b3c5d7e9f1a2b4c6d8e0f1a3b5c7d9e1f2a4b6c8d";
+
+    pub(crate) fn registered(blob_id: &str, name: &str) -> RegisteredModel {
+        RegisteredModel {
+            blob_id: blob_id.to_string(),
+            object_id: format!("0x{}", "ab".repeat(32)),
+            name: name.to_string(),
+            description: format!("{} on Walrus", name),
+            uploader: format!("0x{}", "cd".repeat(32)),
+            uploaded_at: 1_730_000_000_000,
+        }
+    }
+
+    /// Fullnode answering like mainnet for a registry holding `models`
+    async fn rpc(
+        State(models): State<Arc<Vec<RegisteredModel>>>,
+        Json(request): Json<Value>,
+    ) -> Json<Value> {
+        let params = &request["params"];
+        let result = match request["method"].as_str().unwrap() {
+            "sui_getObject" if params[0] == REGISTRY_ID => json!({"data": {
+                "objectId": REGISTRY_ID,
+                "content": {
+                    "dataType": "moveObject",
+                    "type": "0x1234::registry::ModelRegistry",
+                    "fields": {
+                        "id": {"id": REGISTRY_ID},
+                        "blob_ids": models.iter().map(|m| &m.blob_id).collect::<Vec<_>>(),
+                        "models": {
+                            "type": "0x2::table::Table<0x1::string::String, 0x1234::registry::Model>",
+                            "fields": {"id": {"id": TABLE_ID}, "size": models.len().to_string()}
+                        }
+                    }
+                }
+            }}),
+            "sui_getObject" => json!({"error": {"code": "notExists", "object_id": params[0]}}),
+            "suix_getDynamicFieldObject" => {
+                assert_eq!(params[0], TABLE_ID);
+                assert_eq!(params[1]["type"], "0x1::string::String");
+                match models
+                    .iter()
+                    .find(|m| params[1]["value"] == m.blob_id.as_str())
+                {
+                    Some(m) => json!({"data": {"content": {
+                        "dataType": "moveObject",
+                        "fields": {
+                            "id": {"id": "0x77"},
+                            "name": m.blob_id,
+                            "value": {
+                                "type": "0x1234::registry::Model",
+                                "fields": {
+                                    "uploader": m.uploader,
+                                    "uploaded_at": m.uploaded_at.to_string(),
+                                    "name": m.name,
+                                    "description": m.description,
+                                    "blob_id": m.blob_id,
+                                    "object_id": m.object_id
+                                }
+                            }
+                        }
+                    }}}),
+                    None => {
+                        json!({"error": {"code": "dynamicFieldNotFound", "parent_object_id": TABLE_ID}})
+                    }
+                }
+            }
+            method => {
+                return Json(json!({
+                    "jsonrpc": "2.0", "id": request["id"],
+                    "error": {"code": -32601, "message": format!("Method not found: {}", method)}
+                }))
+            }
+        };
+        Json(json!({"jsonrpc": "2.0", "id": request["id"], "result": result}))
+    }
+
+    /// Mock fullnode URL serving a registry with `models`
+    pub(crate) async fn spawn_rpc(models: Vec<RegisteredModel>) -> String {
+        let app = axum::Router::new()
+            .route("/", axum::routing::post(rpc))
+            .with_state(Arc::new(models));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_read_registry() {
+        let models = vec![
+            registered("M4hsZGQ1oCktdzegB6HnI6Mi28S2nqOPHxK-W7_4BUk", "digits"),
+            registered("q2Zt9dDGk1s8Q3n0hQ7yYlWcF4Xo5VbM6aRr1uEeTpA", "sentiment"),
+        ];
+        let client = SuiRegistryClient::new(SuiRegistryConfig::new(
+            spawn_rpc(models.clone()).await,
+            REGISTRY_ID,
+        ))
+        .unwrap();
+
+        assert_eq!(client.list_models().await.unwrap(), models);
+        assert_eq!(client.resolve(&models[1].blob_id).await.unwrap(), models[1]);
+        assert!(matches!(
+            client.resolve("unknown").await,
+            Err(EnclaveError::ModelNotFound(_))
+        ));
+
+        let wrong_registry = SuiRegistryClient::new(SuiRegistryConfig {
+            registry_id: "0x1".to_string(),
+            ..client.config().clone()
+        })
+        .unwrap();
+        assert!(wrong_registry.list_models().await.is_err());
+    }
+}
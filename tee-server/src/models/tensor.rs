@@ -0,0 +1,264 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Typed n-dimensional tensors exchanged with inference engines
+//!
+//! In JSON a tensor is `{"name", "shape", "dtype", "data"}` with `data`
+//! flattened in row-major order, e.g.
+//! `{"name": "input", "shape": [1, 4], "dtype": "f32", "data": [0.1, 0.2, 0.3, 0.4]}`.
+//! `f16` values travel as ordinary JSON numbers.
+
+use crate::{EnclaveError, Result};
+use half::f16;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Element type of a tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DType {
+    F32,
+    F16,
+    I32,
+    I64,
+    U8,
+    Bool,
+    String,
+}
+
+impl DType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DType::F32 => "f32",
+            DType::F16 => "f16",
+            DType::I32 => "i32",
+            DType::I64 => "i64",
+            DType::U8 => "u8",
+            DType::Bool => "bool",
+            DType::String => "string",
+        }
+    }
+}
+
+impl FromStr for DType {
+    type Err = EnclaveError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "f32" => Ok(DType::F32),
+            "f16" => Ok(DType::F16),
+            "i32" => Ok(DType::I32),
+            "i64" => Ok(DType::I64),
+            "u8" => Ok(DType::U8),
+            "bool" => Ok(DType::Bool),
+            "string" => Ok(DType::String),
+            other => Err(EnclaveError::ValidationError(format!(
+                "Unknown tensor dtype {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Flat row-major elements, tagged with their dtype
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "dtype", content = "data", rename_all = "lowercase")]
+pub enum TensorData {
+    F32(Vec<f32>),
+    #[serde(with = "f16_as_f32")]
+    F16(Vec<f16>),
+    I32(Vec<i32>),
+    I64(Vec<i64>),
+    U8(Vec<u8>),
+    Bool(Vec<bool>),
+    String(Vec<String>),
+}
+
+impl TensorData {
+    pub fn dtype(&self) -> DType {
+        match self {
+            TensorData::F32(_) => DType::F32,
+            TensorData::F16(_) => DType::F16,
+            TensorData::I32(_) => DType::I32,
+            TensorData::I64(_) => DType::I64,
+            TensorData::U8(_) => DType::U8,
+            TensorData::Bool(_) => DType::Bool,
+            TensorData::String(_) => DType::String,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            TensorData::F32(values) => values.len(),
+            TensorData::F16(values) => values.len(),
+            TensorData::I32(values) => values.len(),
+            TensorData::I64(values) => values.len(),
+            TensorData::U8(values) => values.len(),
+            TensorData::Bool(values) => values.len(),
+            TensorData::String(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Named tensor whose element count always matches its shape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RawTensor")]
+pub struct Tensor {
+    /// Graph input or output name; empty inputs are bound by position
+    pub name: String,
+    shape: Vec<usize>,
+    #[serde(flatten)]
+    data: TensorData,
+}
+
+impl Tensor {
+    pub fn new(name: impl Into<String>, shape: Vec<usize>, data: TensorData) -> Result<Self> {
+        let name = name.into();
+        let elements = shape
+            .iter()
+            .try_fold(1usize, |elements, &dim| elements.checked_mul(dim))
+            .ok_or_else(|| {
+                EnclaveError::ValidationError(format!(
+                    "Tensor {:?} shape {:?} has too many elements",
+                    name, shape
+                ))
+            })?;
+        if elements != data.len() {
+            return Err(EnclaveError::ValidationError(format!(
+                "Tensor {:?} has shape {:?} ({} elements) but {} values",
+                name,
+                shape,
+                elements,
+                data.len()
+            )));
+        }
+        Ok(Self { name, shape, data })
+    }
+
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    pub fn dtype(&self) -> DType {
+        self.data.dtype()
+    }
+
+    pub fn data(&self) -> &TensorData {
+        &self.data
+    }
+
+    pub fn into_data(self) -> TensorData {
+        self.data
+    }
+
+    /// Elements of an `f32` tensor
+    pub fn as_f32(&self) -> Option<&[f32]> {
+        match &self.data {
+            TensorData::F32(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// Same elements under a new shape with the same element count
+    pub fn reshape(self, shape: Vec<usize>) -> Result<Self> {
+        Self::new(self.name, shape, self.data)
+    }
+}
+
+/// Wire form, checked by [`Tensor::new`] before it becomes a [`Tensor`]
+#[derive(Deserialize)]
+struct RawTensor {
+    #[serde(default)]
+    name: String,
+    shape: Vec<usize>,
+    #[serde(flatten)]
+    data: TensorData,
+}
+
+impl TryFrom<RawTensor> for Tensor {
+    type Error = EnclaveError;
+
+    fn try_from(raw: RawTensor) -> Result<Self> {
+        Tensor::new(raw.name, raw.shape, raw.data)
+    }
+}
+
+/// `f16` elements as plain JSON numbers rather than raw bits
+mod f16_as_f32 {
+    use half::f16;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(values: &[f16], serializer: S) -> Result<S::Ok, S::Error> {
+        values
+            .iter()
+            .map(|value| value.to_f32())
+            .collect::<Vec<_>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<f16>, D::Error> {
+        Ok(Vec::<f32>::deserialize(deserializer)?
+            .into_iter()
+            .map(f16::from_f32)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_tensor_json_roundtrip() {
+        let cases = [
+            json!({"name": "input", "shape": [1, 2], "dtype": "f32", "data": [0.5, -1.25]}),
+            json!({"name": "half", "shape": [2], "dtype": "f16", "data": [0.5, 2.0]}),
+            json!({"name": "ids", "shape": [2, 1], "dtype": "i64", "data": [101, -7]}),
+            json!({"name": "pixels", "shape": [3], "dtype": "u8", "data": [0, 128, 255]}),
+            json!({"name": "mask", "shape": [2], "dtype": "bool", "data": [true, false]}),
+            json!({"name": "text", "shape": [1], "dtype": "string", "data": ["great"]}),
+            json!({"name": "scalar", "shape": [], "dtype": "i32", "data": [42]}),
+        ];
+        for case in cases {
+            let tensor: Tensor = serde_json::from_value(case.clone()).unwrap();
+            assert_eq!(tensor.dtype().as_str(), case["dtype"]);
+            assert_eq!(serde_json::to_value(&tensor).unwrap(), case);
+        }
+    }
+
+    #[test]
+    fn test_tensor_rejects_mismatched_data() {
+        let unnamed: Tensor =
+            serde_json::from_value(json!({"shape": [2], "dtype": "f32", "data": [1.0, 2.0]}))
+                .unwrap();
+        assert_eq!(unnamed.name, "");
+        assert_eq!(unnamed.as_f32(), Some(&[1.0, 2.0][..]));
+
+        for invalid in [
+            json!({"shape": [3], "dtype": "f32", "data": [1.0, 2.0]}),
+            json!({"shape": [1], "dtype": "u8", "data": [256]}),
+            json!({"shape": [1], "dtype": "f64", "data": [1.0]}),
+            json!({"shape": [1], "dtype": "bool", "data": [1]}),
+            // Wraps to 0 elements without the overflow check
+            json!({"shape": [1u64 << 32, 1u64 << 32], "dtype": "f32", "data": []}),
+        ] {
+            assert!(serde_json::from_value::<Tensor>(invalid).is_err());
+        }
+
+        let tensor = Tensor::new("x", vec![4], TensorData::I32(vec![1, 2, 3, 4])).unwrap();
+        assert_eq!(tensor.clone().reshape(vec![2, 2]).unwrap().shape(), [2, 2]);
+        assert!(tensor.reshape(vec![3]).is_err());
+    }
+}
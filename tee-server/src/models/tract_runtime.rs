@@ -0,0 +1,250 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! tract inference engine
+//!
+//! Loads the same `.onnx` files as ONNX Runtime but is pure Rust, so the
+//! enclave image links no native libraries and builds reproducibly.
+
+use crate::models::{bind_inputs, BackendKind, InferenceBackend, Tensor, TensorData, TensorInfo};
+use crate::{EnclaveError, Result};
+use std::path::Path;
+use tracing::{debug, info};
+use tract_onnx::prelude::*;
+
+/// Optimized tract plan for one model
+pub struct TractBackend {
+    model_path: String,
+    plan: TypedRunnableModel<TypedModel>,
+    inputs: Vec<TensorInfo>,
+    outputs: Vec<TensorInfo>,
+    size_bytes: usize,
+}
+
+impl InferenceBackend for TractBackend {
+    fn from_bytes(path: &Path, bytes: &[u8]) -> Result<Self> {
+        let model_path = path.to_string_lossy().to_string();
+        info!("Loading ONNX model into tract from: {}", model_path);
+
+        let model = tract_onnx::onnx()
+            .model_for_read(&mut &bytes[..])
+            .and_then(|model| model.into_typed())
+            .map_err(|e| tract_err("load", path, e))?;
+
+        let inputs = model
+            .input_outlets()
+            .and_then(|outlets| outlets.iter().map(|&o| tensor_info(&model, o)).collect())
+            .map_err(|e| tract_err("read inputs of", path, e))?;
+        let outputs = model
+            .output_outlets()
+            .and_then(|outlets| outlets.iter().map(|&o| tensor_info(&model, o)).collect())
+            .map_err(|e| tract_err("read outputs of", path, e))?;
+
+        let plan = model
+            .into_optimized()
+            .and_then(|model| model.into_runnable())
+            .map_err(|e| tract_err("optimize", path, e))?;
+
+        Ok(Self {
+            model_path,
+            plan,
+            inputs,
+            outputs,
+            // The weights are kept in memory
+            size_bytes: bytes.len(),
+        })
+    }
+
+    fn kind(&self) -> BackendKind {
+        BackendKind::Tract
+    }
+
+    fn inputs(&self) -> &[TensorInfo] {
+        &self.inputs
+    }
+
+    fn outputs(&self) -> &[TensorInfo] {
+        &self.outputs
+    }
+
+    fn run(&self, inputs: &[Tensor]) -> Result<Vec<Tensor>> {
+        debug!(
+            "Running inference with {} on {} inputs",
+            self.model_path,
+            inputs.len()
+        );
+
+        let values = bind_inputs(&self.inputs, inputs)?
+            .into_iter()
+            .map(|tensor| to_tract_value(tensor).map(TValue::from))
+            .collect::<TractResult<TVec<_>>>()
+            .map_err(run_err)?;
+
+        let outputs = self.plan.run(values).map_err(run_err)?;
+        outputs
+            .iter()
+            .zip(&self.outputs)
+            .map(|(value, info)| from_tract_value(&info.name, value))
+            .collect()
+    }
+
+    fn memory_footprint(&self) -> usize {
+        self.size_bytes
+    }
+}
+
+/// Outlet name and declared shape, `-1` for symbolic dimensions
+fn tensor_info(model: &TypedModel, outlet: OutletId) -> TractResult<TensorInfo> {
+    let fact = model.outlet_fact(outlet)?;
+    let name = model
+        .outlet_label(outlet)
+        .unwrap_or(&model.node(outlet.node).name);
+    Ok(TensorInfo {
+        name: name.to_string(),
+        shape: fact
+            .shape
+            .iter()
+            .map(|dim| dim.to_i64().unwrap_or(-1))
+            .collect(),
+    })
+}
+
+fn to_tract_value(tensor: &Tensor) -> TractResult<tract_onnx::prelude::Tensor> {
+    let shape = tensor.shape();
+    match tensor.data() {
+        TensorData::F32(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        TensorData::F16(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        TensorData::I32(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        TensorData::I64(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        TensorData::U8(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        TensorData::Bool(data) => tract_onnx::prelude::Tensor::from_shape(shape, data),
+        // `from_shape` copies plain data only
+        TensorData::String(data) => {
+            Ok(tract_ndarray::ArrayD::from_shape_vec(shape, data.clone())?.into())
+        }
+    }
+}
+
+fn from_tract_value(name: &str, value: &TValue) -> Result<Tensor> {
+    let data = match value.datum_type() {
+        DatumType::F32 => value.as_slice().map(|data| TensorData::F32(data.to_vec())),
+        DatumType::F16 => value.as_slice().map(|data| TensorData::F16(data.to_vec())),
+        DatumType::I32 => value.as_slice().map(|data| TensorData::I32(data.to_vec())),
+        DatumType::I64 => value.as_slice().map(|data| TensorData::I64(data.to_vec())),
+        DatumType::U8 => value.as_slice().map(|data| TensorData::U8(data.to_vec())),
+        DatumType::Bool => value.as_slice().map(|data| TensorData::Bool(data.to_vec())),
+        DatumType::String => value
+            .as_slice()
+            .map(|data| TensorData::String(data.to_vec())),
+        other => {
+            return Err(EnclaveError::InferenceError(format!(
+                "Output {} has unsupported element type {:?}",
+                name, other
+            )))
+        }
+    }
+    .map_err(run_err)?;
+    Tensor::new(name, value.shape().to_vec(), data)
+}
+
+fn run_err(e: TractError) -> EnclaveError {
+    EnclaveError::InferenceError(format!("tract: {}", e))
+}
+
+fn tract_err(action: &str, path: &Path, e: TractError) -> EnclaveError {
+    EnclaveError::InferenceError(format!("tract failed to {} {:?}: {}", action, path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference outputs from `tests/fixtures/models/make_tiny_mlp.py`
+    const TINY_MLP_CASES: [([f32; 4], [f32; 3]); 3] = [
+        ([0.0, 0.0, 0.0, 0.0], [0.3662963, 0.3484318, 0.2852719]),
+        ([1.0, 2.0, 3.0, 4.0], [0.9971478, 0.002836013, 0.000016142]),
+        ([-1.5, 0.5, 2.5, -0.25], [0.4054793, 0.448124, 0.1463967]),
+    ];
+
+    const PARITY_TOLERANCE: f32 = 1e-5;
+
+    fn tiny_mlp() -> std::path::PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/models/tiny_mlp.onnx")
+    }
+
+    fn row(name: &str, values: &[f32]) -> Tensor {
+        Tensor::new(
+            name,
+            vec![1, values.len()],
+            TensorData::F32(values.to_vec()),
+        )
+        .unwrap()
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(
+                (a - e).abs() < PARITY_TOLERANCE,
+                "{:?} != {:?}",
+                actual,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_tract_matches_reference_outputs() {
+        let model = BackendKind::Tract.load(&tiny_mlp()).unwrap();
+        assert_eq!(model.kind(), BackendKind::Tract);
+        assert_eq!(
+            model.inputs(),
+            [TensorInfo {
+                name: "input".to_string(),
+                shape: vec![-1, 4],
+            }]
+        );
+        assert_eq!(model.outputs()[0].name, "probabilities");
+        assert_eq!(
+            model.memory_footprint() as u64,
+            std::fs::metadata(tiny_mlp()).unwrap().len()
+        );
+
+        for (input, expected) in TINY_MLP_CASES {
+            let outputs = model.run(&[row("input", &input)]).unwrap();
+            assert_eq!(outputs[0].name, "probabilities");
+            assert_eq!(outputs[0].shape(), [1, 3]);
+            assert_close(outputs[0].as_f32().unwrap(), &expected);
+        }
+
+        // Symbolic batch dimension, positional input
+        let batch: Vec<f32> = TINY_MLP_CASES
+            .iter()
+            .flat_map(|(input, _)| *input)
+            .collect();
+        let batch = Tensor::new("", vec![3, 4], TensorData::F32(batch)).unwrap();
+        let expected: Vec<f32> = TINY_MLP_CASES.iter().flat_map(|(_, out)| *out).collect();
+        let outputs = model.run(&[batch]).unwrap();
+        assert_eq!(outputs[0].shape(), [3, 3]);
+        assert_close(outputs[0].as_f32().unwrap(), &expected);
+
+        assert!(model.run(&[row("input", &[1.0, 2.0])]).is_err());
+    }
+
+    /// Needs the native library: `ORT_DYLIB_PATH=... cargo test -- --ignored`
+    #[cfg(feature = "onnx-ort")]
+    #[test]
+    #[ignore = "needs ORT_DYLIB_PATH"]
+    fn test_tract_parity_with_onnx_runtime() {
+        let reference = BackendKind::Ort.load(&tiny_mlp()).unwrap();
+        let tract = BackendKind::Tract.load(&tiny_mlp()).unwrap();
+        assert_eq!(tract.inputs(), reference.inputs());
+        for (input, _) in TINY_MLP_CASES {
+            let input = [row("input", &input)];
+            assert_close(
+                tract.run(&input).unwrap()[0].as_f32().unwrap(),
+                reference.run(&input).unwrap()[0].as_f32().unwrap(),
+            );
+        }
+    }
+}
@@ -0,0 +1,584 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Model downloads from Walrus
+//!
+//! Blobs are read from the aggregator HTTP API (`GET /v1/blobs/{blob_id}`)
+//! into a content-addressed cache:
+//!
+//! ```text
+//! <cache_dir>/sha256/<hex>        verified blob contents
+//! <cache_dir>/blob-ids/<blob_id>  hex SHA-256 of the blob
+//! <cache_dir>/partial/<blob_id>   download in progress
+//! ```
+//!
+//! An interrupted download is resumed with a `Range` request, from the same
+//! or the next aggregator. Aggregators are tried in order, in rounds with
+//! exponential backoff between them. A blob whose digest differs from the
+//! expected one is discarded and never cached. Cached blobs are rehashed on
+//! every hit, and concurrent fetches of one blob share a single download.
+
+use crate::{EnclaveError, Result};
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tracing::{debug, info, warn};
+
+/// Where and how blobs are downloaded
+#[derive(Debug, Clone)]
+pub struct WalrusConfig {
+    /// Aggregator base URLs, tried in order
+    pub aggregators: Vec<String>,
+    pub cache_dir: PathBuf,
+    /// Passes over every aggregator before giving up
+    pub max_rounds: u32,
+    /// Wait after the first failed round, doubled after each one
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Per-request timeout, including the body
+    pub request_timeout: Duration,
+    /// HTTP proxy for every request, e.g. the enclave's egress relay
+    pub proxy: Option<String>,
+}
+
+impl WalrusConfig {
+    pub fn new(aggregators: Vec<String>, cache_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            aggregators,
+            cache_dir: cache_dir.into(),
+            max_rounds: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(600),
+            proxy: None,
+        }
+    }
+}
+
+/// Blob stored in the cache
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedBlob {
+    pub blob_id: String,
+    pub path: PathBuf,
+    /// Hex-encoded SHA-256 of the contents
+    pub sha256: String,
+}
+
+/// Walrus aggregator client with an on-disk blob cache
+pub struct WalrusClient {
+    config: WalrusConfig,
+    http: reqwest::Client,
+    /// Per-blob lock held while a blob is checked and downloaded
+    downloads: Mutex<HashMap<String, Weak<tokio::sync::Mutex<()>>>>,
+}
+
+impl WalrusClient {
+    pub fn new(config: WalrusConfig) -> Result<Self> {
+        if config.aggregators.is_empty() {
+            return Err(EnclaveError::ValidationError(
+                "At least one Walrus aggregator is required".to_string(),
+            ));
+        }
+        let mut http = reqwest::Client::builder().timeout(config.request_timeout);
+        if let Some(proxy) = &config.proxy {
+            http = http.proxy(reqwest::Proxy::all(proxy).map_err(|e| {
+                EnclaveError::ValidationError(format!("Invalid proxy {:?}: {}", proxy, e))
+            })?);
+        }
+        let http = http
+            .build()
+            .map_err(|e| EnclaveError::GenericError(format!("Walrus HTTP client: {}", e)))?;
+        Ok(Self {
+            config,
+            http,
+            downloads: Mutex::default(),
+        })
+    }
+
+    pub fn config(&self) -> &WalrusConfig {
+        &self.config
+    }
+
+    /// Path of `blob_id` in the cache, downloading it first if needed
+    ///
+    /// With `expected_sha256` the blob must have that hex SHA-256, cached or
+    /// not.
+    pub async fn fetch(&self, blob_id: &str, expected_sha256: Option<&str>) -> Result<FetchedBlob> {
+        validate_blob_id(blob_id)?;
+        // Waiters find the blob cached once the first download finishes
+        let lock = self.download_lock(blob_id);
+        let _downloading = lock.lock().await;
+        if let Some(blob) = self.cached(blob_id).await? {
+            check_digest(&blob, expected_sha256)?;
+            debug!("Walrus blob {} found in cache", blob_id);
+            return Ok(blob);
+        }
+
+        let partial = self.dir("partial")?.join(blob_id);
+        let mut backoff = self.config.initial_backoff;
+        let mut last_error = None;
+        for round in 1..=self.config.max_rounds {
+            for aggregator in &self.config.aggregators {
+                let downloaded = match self.download(aggregator, blob_id, &partial).await {
+                    Ok(()) => self.store(blob_id, &partial, expected_sha256).await,
+                    Err(e) => Err(e),
+                };
+                match downloaded {
+                    Ok(blob) => {
+                        info!("Fetched Walrus blob {} from {}", blob_id, aggregator);
+                        return Ok(blob);
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Fetching Walrus blob {} from {} failed (round {}): {}",
+                            blob_id, aggregator, round, e
+                        );
+                        last_error = Some(e);
+                    }
+                }
+            }
+            if round < self.config.max_rounds {
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(self.config.max_backoff);
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            EnclaveError::GenericError(format!("Walrus blob {} was not fetched", blob_id))
+        }))
+    }
+
+    /// Append the rest of the blob to `partial`, or rewrite it if the
+    /// aggregator ignores the range
+    async fn download(&self, aggregator: &str, blob_id: &str, partial: &Path) -> Result<()> {
+        let offset = std::fs::metadata(partial).map(|m| m.len()).unwrap_or(0);
+        let url = format!("{}/v1/blobs/{}", aggregator.trim_end_matches('/'), blob_id);
+        let mut request = self.http.get(&url);
+        if offset > 0 {
+            debug!("Resuming Walrus blob {} at byte {}", blob_id, offset);
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await.map_err(|e| fetch_err(&url, e))?;
+
+        let append = match response.status() {
+            StatusCode::OK => false,
+            StatusCode::PARTIAL_CONTENT if offset > 0 => {
+                let range_start = response
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.strip_prefix("bytes "))
+                    .and_then(|range| range.split('-').next())
+                    .and_then(|start| start.parse::<u64>().ok());
+                if range_start != Some(offset) {
+                    return Err(EnclaveError::GenericError(format!(
+                        "{} answered a range from byte {} with {:?}",
+                        url,
+                        offset,
+                        response.headers().get(CONTENT_RANGE)
+                    )));
+                }
+                true
+            }
+            StatusCode::RANGE_NOT_SATISFIABLE => {
+                // Start over rather than trust a partial file the aggregator disowns
+                remove_file(partial)?;
+                return Err(EnclaveError::GenericError(format!(
+                    "{} rejected resuming at byte {}",
+                    url, offset
+                )));
+            }
+            StatusCode::NOT_FOUND => {
+                return Err(EnclaveError::ModelNotFound(format!(
+                    "Walrus blob {} not found at {}",
+                    blob_id, aggregator
+                )))
+            }
+            status => {
+                return Err(EnclaveError::GenericError(format!(
+                    "{} returned {}",
+                    url, status
+                )))
+            }
+        };
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(partial)
+            .await
+            .map_err(|e| cache_err(partial, e))?;
+        // Keep what arrived so far, the next attempt resumes from there
+        let mut body_error = None;
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => file
+                    .write_all(&chunk)
+                    .await
+                    .map_err(|e| cache_err(partial, e))?,
+                Ok(None) => break,
+                Err(e) => {
+                    body_error = Some(fetch_err(&url, e));
+                    break;
+                }
+            }
+        }
+        file.flush().await.map_err(|e| cache_err(partial, e))?;
+        match body_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    fn download_lock(&self, blob_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut downloads = self.downloads.lock().unwrap();
+        if let Some(lock) = downloads.get(blob_id).and_then(Weak::upgrade) {
+            return lock;
+        }
+        downloads.retain(|_, lock| lock.strong_count() > 0);
+        let lock = Arc::new(tokio::sync::Mutex::new(()));
+        downloads.insert(blob_id.to_string(), Arc::downgrade(&lock));
+        lock
+    }
+
+    /// Hash a completed download and move it to its content address
+    async fn store(
+        &self,
+        blob_id: &str,
+        partial: &Path,
+        expected_sha256: Option<&str>,
+    ) -> Result<FetchedBlob> {
+        let sha256 = sha256_file(partial).await?;
+        let blob = FetchedBlob {
+            blob_id: blob_id.to_string(),
+            path: self.dir("sha256")?.join(&sha256),
+            sha256,
+        };
+        if let Err(e) = check_digest(&blob, expected_sha256) {
+            remove_file(partial)?;
+            return Err(e);
+        }
+
+        std::fs::rename(partial, &blob.path).map_err(|e| cache_err(&blob.path, e))?;
+        let index = self.dir("blob-ids")?.join(blob_id);
+        std::fs::write(&index, &blob.sha256).map_err(|e| cache_err(&index, e))?;
+        Ok(blob)
+    }
+
+    /// Cached copy of `blob_id`, rehashed; one that no longer has its
+    /// recorded digest is removed so it is downloaded again
+    async fn cached(&self, blob_id: &str) -> Result<Option<FetchedBlob>> {
+        let index = self.config.cache_dir.join("blob-ids").join(blob_id);
+        let Ok(sha256) = std::fs::read_to_string(&index) else {
+            return Ok(None);
+        };
+        let sha256 = sha256.trim().to_string();
+        let path = self.config.cache_dir.join("sha256").join(&sha256);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let actual = sha256_file(&path).await?;
+        if actual != sha256 {
+            warn!(
+                "Cached Walrus blob {} has SHA-256 {}, recorded {}; downloading it again",
+                blob_id, actual, sha256
+            );
+            remove_file(&path)?;
+            remove_file(&index)?;
+            return Ok(None);
+        }
+        Ok(Some(FetchedBlob {
+            blob_id: blob_id.to_string(),
+            path,
+            sha256,
+        }))
+    }
+
+    fn dir(&self, name: &str) -> Result<PathBuf> {
+        let dir = self.config.cache_dir.join(name);
+        std::fs::create_dir_all(&dir).map_err(|e| cache_err(&dir, e))?;
+        Ok(dir)
+    }
+}
+
+/// Walrus blob ids are URL-safe base64, which also keeps them safe as file names
+fn validate_blob_id(blob_id: &str) -> Result<()> {
+    let valid = !blob_id.is_empty()
+        && blob_id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(EnclaveError::ValidationError(format!(
+            "Invalid Walrus blob id {:?}",
+            blob_id
+        )))
+    }
+}
+
+fn check_digest(blob: &FetchedBlob, expected_sha256: Option<&str>) -> Result<()> {
+    match expected_sha256 {
+        Some(expected) if !expected.eq_ignore_ascii_case(&blob.sha256) => {
+            Err(EnclaveError::IntegrityError(format!(
+                "Walrus blob {} has SHA-256 {}, expected {}",
+                blob.blob_id, blob.sha256, expected
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Hex SHA-256 of a file, read on the blocking pool
+async fn sha256_file(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path).map_err(|e| cache_err(&path, e))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = [0u8; 64 * 1024];
+        loop {
+            let read = file.read(&mut buffer).map_err(|e| cache_err(&path, e))?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(hex::encode(hasher.finalize()))
+    })
+    .await
+    .map_err(|e| EnclaveError::GenericError(format!("Hashing task failed: {}", e)))?
+}
+
+fn remove_file(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(cache_err(path, e)),
+        _ => Ok(()),
+    }
+}
+
+fn fetch_err(url: &str, e: reqwest::Error) -> EnclaveError {
+    EnclaveError::GenericError(format!("Failed to fetch {}: {}", url, e))
+}
+
+fn cache_err(path: &Path, e: std::io::Error) -> EnclaveError {
+    EnclaveError::GenericError(format!("Walrus cache {:?}: {}", path, e))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use axum::extract::{Path as UrlPath, State};
+    use axum::http::{HeaderMap, StatusCode as HttpStatus};
+    use axum::response::{IntoResponse, Response};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncReadExt;
+
+    pub(crate) const BLOB_ID: &str = "M4hsZGQ1oCktdzegB6HnI6Mi28S2nqOPHxK-W7_4BUk";
+
+    pub(crate) fn blob() -> Vec<u8> {
+        (0..100_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    /// Aggregator serving `BLOB_ID`, failing its first `failures` requests
+    #[derive(Default)]
+    pub(crate) struct MockAggregator {
+        failures: AtomicUsize,
+        /// `Range` header of every request
+        ranges: Mutex<Vec<Option<String>>>,
+    }
+
+    async fn serve_blob(
+        State(mock): State<Arc<MockAggregator>>,
+        UrlPath(blob_id): UrlPath<String>,
+        headers: HeaderMap,
+    ) -> Response {
+        let range = headers
+            .get("range")
+            .map(|value| value.to_str().unwrap().to_string());
+        mock.ranges.lock().unwrap().push(range.clone());
+        if mock
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok()
+        {
+            return HttpStatus::SERVICE_UNAVAILABLE.into_response();
+        }
+        if blob_id != BLOB_ID {
+            return HttpStatus::NOT_FOUND.into_response();
+        }
+
+        let blob = blob();
+        match range.and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok()) {
+            Some(start) => (
+                HttpStatus::PARTIAL_CONTENT,
+                [(
+                    "content-range",
+                    format!("bytes {}-{}/{}", start, blob.len() - 1, blob.len()),
+                )],
+                blob[start..].to_vec(),
+            )
+                .into_response(),
+            None => blob.into_response(),
+        }
+    }
+
+    pub(crate) async fn spawn_aggregator(failures: usize) -> (String, Arc<MockAggregator>) {
+        let mock = Arc::new(MockAggregator {
+            failures: AtomicUsize::new(failures),
+            ..Default::default()
+        });
+        let app = axum::Router::new()
+            .route("/v1/blobs/:blob_id", axum::routing::get(serve_blob))
+            .with_state(mock.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        (url, mock)
+    }
+
+    /// Aggregator that announces the whole blob but hangs up halfway
+    async fn spawn_truncating_aggregator() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let blob = blob();
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    blob.len()
+                );
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(&blob[..blob.len() / 2]).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        url
+    }
+
+    fn config(aggregators: Vec<String>, name: &str) -> WalrusConfig {
+        let cache_dir =
+            std::env::temp_dir().join(format!("walrus-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        WalrusConfig {
+            initial_backoff: Duration::from_millis(10),
+            ..WalrusConfig::new(aggregators, cache_dir)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_retries_and_caches() {
+        let (flaky, mock) = spawn_aggregator(1).await;
+        // Nothing listens on port 1
+        let config = config(vec!["http://127.0.0.1:1".to_string(), flaky], "retries");
+        let cache_dir = config.cache_dir.clone();
+        let client = WalrusClient::new(config).unwrap();
+        let sha256 = hex::encode(Sha256::digest(blob()));
+
+        let fetched = client.fetch(BLOB_ID, Some(&sha256)).await.unwrap();
+        assert_eq!(fetched.sha256, sha256);
+        assert_eq!(fetched.path, cache_dir.join("sha256").join(&sha256));
+        assert_eq!(std::fs::read(&fetched.path).unwrap(), blob());
+        assert_eq!(mock.ranges.lock().unwrap().len(), 2);
+
+        // Served from the cache without asking the aggregator again
+        assert_eq!(client.fetch(BLOB_ID, None).await.unwrap(), fetched);
+        assert_eq!(mock.ranges.lock().unwrap().len(), 2);
+        assert!(matches!(
+            client.fetch(BLOB_ID, Some(&"0".repeat(64))).await,
+            Err(EnclaveError::IntegrityError(_))
+        ));
+
+        assert!(matches!(
+            client.fetch("missing", None).await,
+            Err(EnclaveError::ModelNotFound(_))
+        ));
+        assert!(matches!(
+            client.fetch("../keystore", None).await,
+            Err(EnclaveError::ValidationError(_))
+        ));
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_changed_cache_entry_is_downloaded_again() {
+        let (aggregator, mock) = spawn_aggregator(0).await;
+        let config = config(vec![aggregator], "rehash");
+        let cache_dir = config.cache_dir.clone();
+        let client = WalrusClient::new(config).unwrap();
+        let sha256 = hex::encode(Sha256::digest(blob()));
+
+        let fetched = client.fetch(BLOB_ID, Some(&sha256)).await.unwrap();
+        std::fs::write(&fetched.path, b"tampered").unwrap();
+        assert_eq!(client.fetch(BLOB_ID, Some(&sha256)).await.unwrap(), fetched);
+        assert_eq!(std::fs::read(&fetched.path).unwrap(), blob());
+        assert_eq!(mock.ranges.lock().unwrap().len(), 2);
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_fetches_download_once() {
+        let (aggregator, mock) = spawn_aggregator(0).await;
+        let config = config(vec![aggregator], "single-flight");
+        let cache_dir = config.cache_dir.clone();
+        let client = Arc::new(WalrusClient::new(config).unwrap());
+
+        let fetches: Vec<_> = (0..8)
+            .map(|_| {
+                let client = client.clone();
+                tokio::spawn(async move { client.fetch(BLOB_ID, None).await })
+            })
+            .collect();
+        for fetch in fetches {
+            let fetched = fetch.await.unwrap().unwrap();
+            assert_eq!(std::fs::read(&fetched.path).unwrap(), blob());
+        }
+        assert_eq!(mock.ranges.lock().unwrap().len(), 1);
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_resumes_interrupted_download() {
+        let truncating = spawn_truncating_aggregator().await;
+        let (resuming, mock) = spawn_aggregator(0).await;
+        let config = config(vec![truncating, resuming], "resume");
+        let cache_dir = config.cache_dir.clone();
+        let client = WalrusClient::new(config).unwrap();
+
+        let fetched = client.fetch(BLOB_ID, None).await.unwrap();
+        assert_eq!(std::fs::read(&fetched.path).unwrap(), blob());
+        assert_eq!(
+            *mock.ranges.lock().unwrap(),
+            vec![Some(format!("bytes={}-", blob().len() / 2))]
+        );
+        assert!(!cache_dir.join("partial").join(BLOB_ID).exists());
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_wrong_digest() {
+        let (aggregator, mock) = spawn_aggregator(0).await;
+        let config = WalrusConfig {
+            max_rounds: 2,
+            ..config(vec![aggregator], "digest")
+        };
+        let cache_dir = config.cache_dir.clone();
+        let client = WalrusClient::new(config).unwrap();
+
+        let fetched = client.fetch(BLOB_ID, Some(&"ab".repeat(32))).await;
+        assert!(matches!(fetched, Err(EnclaveError::IntegrityError(_))));
+        // Each round downloads from scratch and nothing is kept
+        assert_eq!(*mock.ranges.lock().unwrap(), vec![None, None]);
+        assert!(!cache_dir.join("blob-ids").join(BLOB_ID).exists());
+        assert!(!cache_dir.join("partial").join(BLOB_ID).exists());
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+}
@@ -0,0 +1,557 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! HTTP server: routes and handlers over [`AppState`]
+//!
+//! [`ServerBuilder`] assembles the state from a signing key, attestation
+//! provider and model loader and returns an axum [`Router`], so the server
+//! can be mounted in other binaries or run in-process in tests:
+//!
+//! ```no_run
+//! # async fn serve() -> anyhow::Result<()> {
+//! use synapsemodel_tee_server::{ServerBuilder, ServerConfig};
+//!
+//! let app = ServerBuilder::new(ServerConfig::default())
+//!     .keypair(synapsemodel_tee_server::common::generate_keypair())
+//!     .build();
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+//! axum::serve(listener, app).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::apps::synapsemodel::process_inference;
+use crate::common::{
+    AttestationParams, AttestationProvider, AttestationResponse, HealthResponse, IntentConfig,
+    Keystore, ModelListResponse, ModelSchemaParams, PublicKeyResponse,
+};
+use crate::models::{ModelLoader, ModelMeasurements, ModelSchema};
+use crate::AppState;
+use axum::{
+    extract::{DefaultBodyLimit, Path, Query, State},
+    http::{header, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use fastcrypto::ed25519::Ed25519KeyPair;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tower_http::timeout::TimeoutLayer;
+use tracing::warn;
+
+/// Settings of the routes themselves, independent of how they are served
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerConfig {
+    /// Intent version and app id embedded in signed responses
+    pub intent: IntentConfig,
+    /// Origins allowed by CORS, `*` for any; empty adds no CORS headers
+    pub cors_origins: Vec<String>,
+    /// Largest accepted request body
+    pub max_request_bytes: usize,
+    /// Requests running longer are answered with 408
+    pub request_timeout: Option<Duration>,
+}
+
+impl Default for ServerConfig {
+    /// Settings of a default [`Config`](crate::config::Config)
+    fn default() -> Self {
+        crate::config::Config::default().server_config()
+    }
+}
+
+/// Assembles the server state and router
+///
+/// Anything left unset falls back to what [`AppState::new`] uses: a fresh
+/// ephemeral key, Nitro attestation and an empty model loader. The state is
+/// built from these inputs alone; the binary reads the environment into
+/// them through [`Config`](crate::config::Config).
+pub struct ServerBuilder {
+    config: ServerConfig,
+    keystore: Option<Keystore>,
+    attestation: Option<Arc<dyn AttestationProvider>>,
+    model_loader: Option<ModelLoader>,
+}
+
+impl ServerBuilder {
+    pub fn new(config: ServerConfig) -> Self {
+        Self {
+            config,
+            keystore: None,
+            attestation: None,
+            model_loader: None,
+        }
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    /// Sign with `keypair`, never rotated
+    pub fn keypair(self, keypair: Ed25519KeyPair) -> Self {
+        let now_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.keystore(Keystore::ephemeral(keypair, now_ms))
+    }
+
+    /// Sign with the current key of `keystore`, e.g. from `Keystore::open`
+    pub fn keystore(mut self, keystore: Keystore) -> Self {
+        self.keystore = Some(keystore);
+        self
+    }
+
+    pub fn attestation_provider(mut self, provider: Arc<dyn AttestationProvider>) -> Self {
+        self.attestation = Some(provider);
+        self
+    }
+
+    pub fn model_loader(mut self, model_loader: ModelLoader) -> Self {
+        self.model_loader = Some(model_loader);
+        self
+    }
+
+    /// Shared state, for callers that use it before serving
+    pub fn build_state(self) -> Arc<AppState> {
+        self.into_parts().0
+    }
+
+    /// Router over freshly built state
+    pub fn build(self) -> Router {
+        let (state, config) = self.into_parts();
+        router(state, &config)
+    }
+
+    fn into_parts(self) -> (Arc<AppState>, ServerConfig) {
+        let mut state = match self.keystore {
+            Some(keystore) => AppState::with_keystore(keystore),
+            None => AppState::new(crate::common::generate_keypair()),
+        };
+        state.intent_config = self.config.intent;
+        if let Some(provider) = self.attestation {
+            state = state.with_attestation_provider(provider);
+        }
+        if let Some(model_loader) = self.model_loader {
+            state = state.with_model_loader(model_loader);
+        }
+        (Arc::new(state), self.config)
+    }
+}
+
+/// Application router over `state`
+pub fn router(state: Arc<AppState>, config: &ServerConfig) -> Router {
+    let router = Router::new()
+        // Health check
+        .route("/health_check", get(health_check))
+        // Public key endpoint
+        .route("/get_pk", get(public_key))
+        // Loaded model hashes
+        .route("/get_measurements", get(measurements))
+        // Models listed in the manifest
+        .route("/models", get(models))
+        // Model input/output schema
+        .route("/models/:id/schema", get(model_schema))
+        // Signed inference
+        .route("/process_data", post(process_inference))
+        // Attestation endpoint
+        .route(
+            "/get_attestation",
+            get(attestation).post(attestation_with_body),
+        )
+        // Root endpoint
+        .route("/", get(root))
+        // 404 handler
+        .fallback(not_found)
+        .layer(DefaultBodyLimit::max(config.max_request_bytes));
+
+    let router = match config.request_timeout {
+        Some(timeout) => router.layer(TimeoutLayer::new(timeout)),
+        None => router,
+    };
+    let router = match cors_layer(&config.cors_origins) {
+        Some(cors) => router.layer(cors),
+        None => router,
+    };
+    router.with_state(state)
+}
+
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    let allow_origin = match origins {
+        [] => return None,
+        [any] if any == "*" => AllowOrigin::any(),
+        origins => AllowOrigin::list(
+            origins
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        ),
+    };
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+            .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION]),
+    )
+}
+
+/// Root endpoint (ping)
+async fn root() -> &'static str {
+    "Pong!"
+}
+
+/// Health check endpoint
+async fn health_check() -> impl IntoResponse {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+
+    Json(HealthResponse {
+        status: "healthy".to_string(),
+        timestamp,
+        version: "1.0.0".to_string(),
+    })
+}
+
+/// Public key endpoint
+async fn public_key(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    if let Err(e) = state.rotate_keys_if_due(timestamp) {
+        warn!("Key rotation failed: {}", e);
+    }
+
+    let keystore = state.keystore.read().unwrap();
+    let current = keystore.current_epoch();
+
+    Json(PublicKeyResponse {
+        public_key: current.public_key.clone(),
+        format: "ed25519-hex".to_string(),
+        key_id: current.key_id.clone(),
+        keys: keystore.epochs().to_vec(),
+    })
+}
+
+/// Hashes and Merkle root of the cached models
+async fn measurements(
+    State(state): State<Arc<AppState>>,
+) -> crate::Result<Json<ModelMeasurements>> {
+    Ok(Json(state.model_loader.measurements()?))
+}
+
+/// Models listed in the manifest
+async fn models(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    Json(ModelListResponse {
+        models: state.model_loader.entries(),
+    })
+}
+
+/// Input/output names, shapes and dtypes of one model
+async fn model_schema(
+    State(state): State<Arc<AppState>>,
+    Path(model_id): Path<String>,
+    Query(params): Query<ModelSchemaParams>,
+) -> crate::Result<Json<ModelSchema>> {
+    state
+        .model_loader
+        .resolve(&model_id, params.model_sha256.as_deref())
+        .await?;
+    state.model_loader.schema(&model_id).map(Json)
+}
+
+/// Attestation endpoint, with an optional `?nonce=<hex>` challenge
+async fn attestation(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<AttestationParams>,
+) -> Response {
+    attest(&state, params)
+}
+
+/// Attestation endpoint taking `{"nonce": "<hex>"}` as a JSON body
+async fn attestation_with_body(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<AttestationParams>,
+) -> Response {
+    attest(&state, params)
+}
+
+fn attest(state: &AppState, params: AttestationParams) -> Response {
+    use crate::common::{get_attestation_document, parse_nonce, AttestationDocument};
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+
+    let nonce = match params.nonce.as_deref().map(parse_nonce).transpose() {
+        Ok(nonce) => nonce,
+        Err(e) => return e.into_response(),
+    };
+
+    // Bind the current signing key and loaded models into the document
+    let user_data = match state.attestation_user_data() {
+        Ok(user_data) => user_data,
+        Err(e) => return e.into_response(),
+    };
+
+    let provider = state.attestation.kind();
+    match get_attestation_document(
+        state.attestation.as_ref(),
+        &user_data,
+        nonce.as_deref(),
+        None,
+    ) {
+        Ok(doc) => {
+            // SEV-SNP reports and TDX quotes carry no timestamp
+            let document_timestamp = provider
+                .has_pcrs()
+                .then(|| AttestationDocument::from_cose_sign1(&doc).ok())
+                .flatten()
+                .map(|parsed| parsed.timestamp);
+            let attestation_hex = hex::encode(doc);
+            (
+                StatusCode::OK,
+                Json(AttestationResponse {
+                    provider,
+                    attestation: attestation_hex,
+                    timestamp,
+                    nonce: nonce.map(hex::encode),
+                    document_timestamp,
+                }),
+            )
+                .into_response()
+        }
+        Err(e) => {
+            warn!("Attestation not available: {}", e);
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(AttestationResponse {
+                    provider,
+                    attestation: "Attestation not available in development mode".to_string(),
+                    timestamp,
+                    nonce: None,
+                    document_timestamp: None,
+                }),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// 404 handler
+async fn not_found() -> impl IntoResponse {
+    (
+        StatusCode::NOT_FOUND,
+        Json(serde_json::json!({
+            "error": "Not found"
+        })),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::ProviderKind;
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let response = health_check().await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_builder_serves_configured_state() {
+        use fastcrypto::traits::{KeyPair, ToFromBytes};
+        use tower::ServiceExt;
+
+        let keypair = crate::common::generate_keypair();
+        let public_key = hex::encode(keypair.public().as_bytes());
+        let config = ServerConfig {
+            intent: IntentConfig {
+                version: 0,
+                app_id: 7,
+            },
+            cors_origins: Vec::new(),
+            ..ServerConfig::default()
+        };
+        let state = ServerBuilder::new(config.clone())
+            .keypair(keypair)
+            .build_state();
+        assert_eq!(state.intent_config, config.intent);
+        assert!(state.model_loader.list_models().is_empty());
+
+        let app = router(state, &config);
+        let request = |uri: &str| {
+            axum::http::Request::get(uri)
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(request("/get_pk")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: PublicKeyResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.public_key, public_key);
+
+        let response = app.oneshot(request("/public_key")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_attestation_with_mock_nsm() {
+        let kp = crate::common::generate_keypair();
+        let state = Arc::new(
+            AppState::new(kp).with_attestation_provider(ProviderKind::Software.create().unwrap()),
+        );
+
+        let response = attestation(State(state.clone()), Query(AttestationParams::default())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let params = AttestationParams {
+            nonce: Some("c0ffee".to_string()),
+        };
+        let response = attestation_with_body(State(state.clone()), Json(params)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body: AttestationResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body.provider, ProviderKind::Software);
+        assert_eq!(body.nonce.as_deref(), Some("c0ffee"));
+
+        let doc = hex::decode(&body.attestation).unwrap();
+        let doc = crate::common::AttestationDocument::from_cose_sign1(&doc).unwrap();
+        assert_eq!(doc.nonce, Some(vec![0xc0, 0xff, 0xee]));
+        assert_eq!(body.document_timestamp, Some(doc.timestamp));
+        let models_root = state.model_loader.measurements().unwrap().root().unwrap();
+        assert!(doc.binds_models_root(&models_root));
+
+        let params = AttestationParams {
+            nonce: Some("not-hex".to_string()),
+        };
+        let response = attestation(State(state), Query(params)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_model_list_and_schema() {
+        let dir = std::env::temp_dir().join(format!("model-schema-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::copy(
+            concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/tests/fixtures/models/tiny_mlp.onnx"
+            ),
+            dir.join("mnist.onnx"),
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("models.toml"),
+            "[[model]]\nid = \"mnist-classifier\"\nfile = \"mnist.onnx\"\n",
+        )
+        .unwrap();
+        let kp = crate::common::generate_keypair();
+        let state =
+            Arc::new(AppState::new(kp).with_model_loader(ModelLoader::from_dir(&dir).unwrap()));
+
+        let response = models(State(state.clone())).await.into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let listed: ModelListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listed.models.len(), 1);
+        assert_eq!(listed.models[0].file, "mnist.onnx");
+
+        let response = model_schema(
+            State(state.clone()),
+            Path("mnist-classifier".to_string()),
+            Query(ModelSchemaParams::default()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let schema: ModelSchema = serde_json::from_slice(&body).unwrap();
+        assert_eq!(schema.model_id, "mnist-classifier");
+        assert_eq!(schema.inputs[0].name, "input");
+
+        let response = model_schema(
+            State(state),
+            Path("unknown".to_string()),
+            Query(ModelSchemaParams::default()),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "mock-inference")]
+    #[tokio::test]
+    async fn test_process_data_is_signed() {
+        use crate::{
+            apps::synapsemodel::InferenceRequest,
+            common::{ProcessDataRequest, SignedInferenceResponse},
+            verify::verify_response,
+        };
+
+        let dir = std::env::temp_dir().join(format!("process-data-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("mnist.onnx"), b"mock").unwrap();
+        std::fs::write(
+            dir.join("models.toml"),
+            "[[model]]\nid = \"mnist-classifier\"\nfile = \"mnist.onnx\"\nbackend = \"mock\"\n\
+             [model.input]\nfield = \"pixels\"\ndtype = \"f32\"\nshape = [1, 784]\n\
+             [model.postprocessing]\nkind = \"classification\"\n",
+        )
+        .unwrap();
+        let kp = crate::common::generate_keypair();
+        let state =
+            Arc::new(AppState::new(kp).with_model_loader(ModelLoader::from_dir(&dir).unwrap()));
+        let request = |model_id: &str, pixels: usize| {
+            Json(ProcessDataRequest {
+                payload: InferenceRequest {
+                    job_id: "job-1".to_string(),
+                    model_id: model_id.to_string(),
+                    input_data: serde_json::json!({ "pixels": vec![0.0; pixels] }),
+                    model_sha256: None,
+                    model_blake2b256: None,
+                },
+            })
+        };
+
+        let Json(signed): Json<SignedInferenceResponse> =
+            process_inference(State(state.clone()), request("mnist-classifier", 784))
+                .await
+                .unwrap();
+        assert_eq!(signed.response.data.model_id, "mnist-classifier");
+        assert!(signed.response.data.result["prediction"].is_number());
+        let public_key = state
+            .keystore
+            .read()
+            .unwrap()
+            .current_epoch()
+            .public_key
+            .clone();
+        let input = serde_json::json!({ "pixels": vec![0.0; 784] });
+        assert!(verify_response(&signed, &public_key, Some(&input)).is_valid());
+
+        let response = process_inference(State(state.clone()), request("unknown", 784))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = process_inference(State(state), request("mnist-classifier", 100))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
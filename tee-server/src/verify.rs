@@ -0,0 +1,333 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! Offline verification of signed inference responses
+//!
+//! Rebuilds the signed BCS bytes, checks the Ed25519 signature against the
+//! enclave public key and optionally recomputes `input_hash`. Each check is
+//! reported separately so auditors can see exactly what failed.
+
+use crate::common::{compute_input_hash_versioned, KeyEpoch, SignedInferenceResponse};
+use fastcrypto::ed25519::{Ed25519PublicKey, Ed25519Signature};
+use fastcrypto::traits::{ToFromBytes, VerifyingKey};
+use serde::Serialize;
+
+/// Outcome of a single verification check
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "lowercase")]
+pub enum CheckStatus {
+    Passed,
+    Failed(String),
+    Skipped(String),
+}
+
+/// A named verification check
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    #[serde(flatten)]
+    pub status: CheckStatus,
+}
+
+/// Result of verifying one response
+#[derive(Debug, Clone, Serialize)]
+pub struct VerificationReport {
+    pub checks: Vec<Check>,
+    /// Hex-encoded BCS bytes covered by the signature, if they could be rebuilt
+    pub signed_bytes: Option<String>,
+}
+
+impl VerificationReport {
+    /// True when no check failed (skipped checks do not count as failures)
+    pub fn is_valid(&self) -> bool {
+        !self
+            .checks
+            .iter()
+            .any(|c| matches!(c.status, CheckStatus::Failed(_)))
+    }
+
+    /// Look up a check by name
+    pub fn check(&self, name: &str) -> Option<&CheckStatus> {
+        self.checks
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| &c.status)
+    }
+
+    fn push(&mut self, name: &'static str, status: CheckStatus) {
+        self.checks.push(Check { name, status });
+    }
+}
+
+/// Decode an Ed25519 public key as served by `/get_pk`
+pub fn parse_public_key(public_key_hex: &str) -> Result<Ed25519PublicKey, String> {
+    let bytes = hex::decode(public_key_hex.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid hex: {}", e))?;
+    Ed25519PublicKey::from_bytes(&bytes).map_err(|e| format!("invalid Ed25519 public key: {}", e))
+}
+
+/// Verify a signed inference response
+///
+/// `input` is the original `input_data`; when absent the input hash check is skipped.
+pub fn verify_response(
+    response: &SignedInferenceResponse,
+    public_key_hex: &str,
+    input: Option<&serde_json::Value>,
+) -> VerificationReport {
+    let mut report = VerificationReport {
+        checks: Vec::new(),
+        signed_bytes: None,
+    };
+    verify_with_public_key(&mut report, response, public_key_hex, input);
+    report
+}
+
+/// Verify a response against the key list served by `/get_pk`
+///
+/// The signing key is selected by the response's `key_id`, so results signed
+/// by a key that has since been rotated out still verify as long as they were
+/// produced inside that key's validity window.
+pub fn verify_response_with_keys(
+    response: &SignedInferenceResponse,
+    keys: &[KeyEpoch],
+    input: Option<&serde_json::Value>,
+) -> VerificationReport {
+    let mut report = VerificationReport {
+        checks: Vec::new(),
+        signed_bytes: None,
+    };
+
+    let Some(epoch) = keys.iter().find(|k| k.key_id == response.key_id) else {
+        report.push(
+            "key_epoch",
+            CheckStatus::Failed(format!("unknown key id '{}'", response.key_id)),
+        );
+        report.push(
+            "signature",
+            CheckStatus::Skipped("no matching key".to_string()),
+        );
+        return report;
+    };
+
+    let timestamp_ms = response.response.timestamp_ms;
+    let epoch_status = if epoch.covers(timestamp_ms) {
+        CheckStatus::Passed
+    } else {
+        CheckStatus::Failed(format!(
+            "timestamp {} outside validity window of epoch {} [{}, {}]",
+            timestamp_ms,
+            epoch.epoch,
+            epoch.not_before_ms,
+            epoch
+                .not_after_ms
+                .map_or_else(|| "current".to_string(), |end| end.to_string())
+        ))
+    };
+    report.push("key_epoch", epoch_status);
+
+    verify_with_public_key(&mut report, response, &epoch.public_key, input);
+    report
+}
+
+fn verify_with_public_key(
+    report: &mut VerificationReport,
+    response: &SignedInferenceResponse,
+    public_key_hex: &str,
+    input: Option<&serde_json::Value>,
+) {
+    let public_key = match parse_public_key(public_key_hex) {
+        Ok(pk) => {
+            report.push("public_key", CheckStatus::Passed);
+            Some(pk)
+        }
+        Err(e) => {
+            report.push("public_key", CheckStatus::Failed(e));
+            None
+        }
+    };
+
+    let message_bytes = match response.response.to_bcs_bytes() {
+        Ok(bytes) => {
+            report.signed_bytes = Some(hex::encode(&bytes));
+            report.push("bcs_encoding", CheckStatus::Passed);
+            Some(bytes)
+        }
+        Err(e) => {
+            report.push("bcs_encoding", CheckStatus::Failed(e.to_string()));
+            None
+        }
+    };
+
+    let signature_status = match (public_key, message_bytes) {
+        (Some(pk), Some(bytes)) => verify_signature(&pk, &bytes, &response.signature),
+        _ => CheckStatus::Skipped("public key or signed bytes unavailable".to_string()),
+    };
+    report.push("signature", signature_status);
+
+    let input_status = match input {
+        Some(input) => {
+            let data = &response.response.data;
+            let version = data.computation_metadata.input_hash_version;
+            let recomputed = compute_input_hash_versioned(input, version);
+            if recomputed.eq_ignore_ascii_case(&data.input_hash) {
+                CheckStatus::Passed
+            } else {
+                CheckStatus::Failed(format!(
+                    "expected {}, recomputed {}",
+                    data.input_hash, recomputed
+                ))
+            }
+        }
+        None => CheckStatus::Skipped("no input supplied".to_string()),
+    };
+    report.push("input_hash", input_status);
+}
+
+fn verify_signature(
+    public_key: &Ed25519PublicKey,
+    message: &[u8],
+    signature_hex: &str,
+) -> CheckStatus {
+    let signature = hex::decode(signature_hex.trim_start_matches("0x"))
+        .map_err(|e| format!("invalid signature hex: {}", e))
+        .and_then(|bytes| {
+            Ed25519Signature::from_bytes(&bytes).map_err(|e| format!("invalid signature: {}", e))
+        });
+
+    match signature {
+        Ok(sig) => match public_key.verify(message, &sig) {
+            Ok(()) => CheckStatus::Passed,
+            Err(_) => CheckStatus::Failed("signature does not match signed bytes".to_string()),
+        },
+        Err(e) => CheckStatus::Failed(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::*;
+    use fastcrypto::ed25519::Ed25519KeyPair;
+    use fastcrypto::traits::KeyPair;
+
+    fn signed_response(kp: &Ed25519KeyPair, input: &serde_json::Value) -> SignedInferenceResponse {
+        let data = InferenceResponse {
+            job_id: "job-1".to_string(),
+            model_id: "mnist-classifier".to_string(),
+            result: serde_json::json!({"prediction": 7, "confidence": 0.95}),
+            input_hash: compute_input_hash(input),
+            computation_metadata: ComputationMetadata {
+                timestamp: 1_700_000_000_000,
+                model_version: "v1.0.0".to_string(),
+                inference_time_ms: 12,
+                input_hash_version: HashVersion::CURRENT,
+                model_sha256: "ab".repeat(32),
+            },
+        };
+        to_signed_response(kp, data, 1_700_000_000_000, IntentScope::ProcessData)
+    }
+
+    fn round_trip(response: &SignedInferenceResponse) -> SignedInferenceResponse {
+        serde_json::from_str(&serde_json::to_string(response).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_valid_response_passes_all_checks() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let input = serde_json::json!({"pixels": [0.0, 0.5]});
+        let response = round_trip(&signed_response(&kp, &input));
+
+        let report = verify_response(&response, &get_public_key_hex(&kp), Some(&input));
+        assert!(report.is_valid(), "{:?}", report);
+        assert_eq!(report.check("signature"), Some(&CheckStatus::Passed));
+        assert_eq!(report.check("input_hash"), Some(&CheckStatus::Passed));
+    }
+
+    #[test]
+    fn test_untagged_hash_version_is_rejected() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let response = signed_response(&kp, &serde_json::json!({}));
+        let mut value = serde_json::to_value(&response).unwrap();
+        value["response"]["data"]["computation_metadata"]
+            .as_object_mut()
+            .unwrap()
+            .remove("input_hash_version");
+        assert!(serde_json::from_value::<SignedInferenceResponse>(value).is_err());
+    }
+
+    #[test]
+    fn test_tampered_result_fails_signature() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let input = serde_json::json!({"pixels": [0.0, 0.5]});
+        let mut response = round_trip(&signed_response(&kp, &input));
+        response.response.data.result = serde_json::json!({"prediction": 1, "confidence": 0.95});
+
+        let report = verify_response(&response, &get_public_key_hex(&kp), Some(&input));
+        assert!(!report.is_valid());
+        assert!(matches!(
+            report.check("signature"),
+            Some(CheckStatus::Failed(_))
+        ));
+        assert_eq!(report.check("input_hash"), Some(&CheckStatus::Passed));
+    }
+
+    #[test]
+    fn test_rotated_key_still_verifies() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let input = serde_json::json!({"pixels": [0.0, 0.5]});
+        let response = signed_response(&kp, &input);
+
+        let mut keystore = Keystore::ephemeral(
+            Ed25519KeyPair::from_bytes(kp.as_ref()).unwrap(),
+            1_600_000_000_000,
+        );
+        keystore.rotate(1_800_000_000_000).unwrap();
+        assert_ne!(keystore.current_epoch().key_id, response.key_id);
+
+        let report = verify_response_with_keys(&response, keystore.epochs(), Some(&input));
+        assert!(report.is_valid(), "{:?}", report);
+        assert_eq!(report.check("key_epoch"), Some(&CheckStatus::Passed));
+
+        // Response timestamp falls after the key was retired
+        let mut keystore = Keystore::ephemeral(kp, 0);
+        keystore.rotate(1).unwrap();
+        let report = verify_response_with_keys(&response, keystore.epochs(), None);
+        assert!(matches!(
+            report.check("key_epoch"),
+            Some(CheckStatus::Failed(_))
+        ));
+    }
+
+    #[test]
+    fn test_wrong_input_and_key_reported_separately() {
+        let kp = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let other = Ed25519KeyPair::generate(&mut rand::thread_rng());
+        let input = serde_json::json!({"pixels": [0.0, 0.5]});
+        let response = signed_response(&kp, &input);
+
+        let wrong_input = serde_json::json!({"pixels": [1.0, 0.5]});
+        let report = verify_response(&response, &get_public_key_hex(&other), Some(&wrong_input));
+        assert!(matches!(
+            report.check("signature"),
+            Some(CheckStatus::Failed(_))
+        ));
+        assert!(matches!(
+            report.check("input_hash"),
+            Some(CheckStatus::Failed(_))
+        ));
+
+        let report = verify_response(&response, "not-hex", None);
+        assert!(matches!(
+            report.check("public_key"),
+            Some(CheckStatus::Failed(_))
+        ));
+        assert!(matches!(
+            report.check("signature"),
+            Some(CheckStatus::Skipped(_))
+        ));
+        assert!(matches!(
+            report.check("input_hash"),
+            Some(CheckStatus::Skipped(_))
+        ));
+    }
+}
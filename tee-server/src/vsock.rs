@@ -0,0 +1,338 @@
+// Copyright (c) 2025, SynapseModel Team
+// SPDX-License-Identifier: Apache-2.0
+
+//! AF_VSOCK transport for Nitro Enclaves
+//!
+//! An enclave has no network interface; its parent instance reaches it over
+//! vsock. [`serve`] runs the same axum router as the TCP server on a
+//! [`VsockListener`], and [`forward_tcp`] runs on the parent to relay TCP
+//! clients to the enclave's port.
+//!
+//! Sockets are non-blocking and driven by tokio's reactor, so no extra
+//! runtime or thread per connection is needed.
+
+use axum::Router;
+use hyper::body::Incoming;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::conn::auto::Builder;
+use hyper_util::service::TowerToHyperService;
+use nix::sys::socket::{
+    self, sockopt, AddressFamily, MsgFlags, Shutdown, SockFlag, SockType, VsockAddr,
+};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, AsyncWrite, Interest, ReadBuf};
+use tokio::net::TcpListener;
+use tower::ServiceExt;
+use tracing::{debug, warn};
+
+/// Any CID of this machine, for listening
+pub const VMADDR_CID_ANY: u32 = u32::MAX;
+/// Loopback, for tests without a VM
+pub const VMADDR_CID_LOCAL: u32 = 1;
+/// The hypervisor host
+pub const VMADDR_CID_HOST: u32 = 2;
+/// The parent instance, as seen from a Nitro Enclave
+pub const NITRO_PARENT_CID: u32 = 3;
+/// Let the kernel pick a free port
+pub const VMADDR_PORT_ANY: u32 = u32::MAX;
+
+/// Non-blocking AF_VSOCK stream socket
+fn stream_socket() -> io::Result<OwnedFd> {
+    Ok(socket::socket(
+        AddressFamily::Vsock,
+        SockType::Stream,
+        SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+        None,
+    )?)
+}
+
+/// Listening vsock socket
+pub struct VsockListener {
+    inner: AsyncFd<OwnedFd>,
+}
+
+impl VsockListener {
+    /// Listen on `port` of `cid`, usually [`VMADDR_CID_ANY`]
+    pub fn bind(cid: u32, port: u32) -> io::Result<Self> {
+        let fd = stream_socket()?;
+        socket::bind(fd.as_raw_fd(), &VsockAddr::new(cid, port))?;
+        socket::listen(&fd, 128)?;
+        Ok(Self {
+            inner: AsyncFd::new(fd)?,
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<VsockAddr> {
+        Ok(socket::getsockname(self.inner.as_raw_fd())?)
+    }
+
+    pub async fn accept(&self) -> io::Result<(VsockStream, VsockAddr)> {
+        let fd = self
+            .inner
+            .async_io(Interest::READABLE, |fd| {
+                let flags = SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC;
+                let raw = socket::accept4(fd.as_raw_fd(), flags)?;
+                // SAFETY: accept4 returned a new descriptor that nothing else owns
+                Ok(unsafe { OwnedFd::from_raw_fd(raw) })
+            })
+            .await?;
+        let peer = socket::getpeername(fd.as_raw_fd())?;
+        Ok((VsockStream::from_fd(fd)?, peer))
+    }
+}
+
+/// Connected vsock stream
+pub struct VsockStream {
+    inner: AsyncFd<OwnedFd>,
+}
+
+impl VsockStream {
+    /// Connect to `port` of `cid`, e.g. an enclave's CID from the parent
+    pub async fn connect(cid: u32, port: u32) -> io::Result<Self> {
+        let fd = stream_socket()?;
+        match socket::connect(fd.as_raw_fd(), &VsockAddr::new(cid, port)) {
+            Ok(()) | Err(nix::errno::Errno::EINPROGRESS) => {}
+            Err(e) => return Err(e.into()),
+        }
+        let stream = Self::from_fd(fd)?;
+        // A non-blocking connect completes once the socket is writable
+        let _ = stream.inner.writable().await?;
+        match socket::getsockopt(stream.inner.get_ref(), sockopt::SocketError)? {
+            0 => Ok(stream),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    /// Wrap a connected, non-blocking stream socket
+    fn from_fd(fd: OwnedFd) -> io::Result<Self> {
+        Ok(Self {
+            inner: AsyncFd::new(fd)?,
+        })
+    }
+
+    pub fn peer_addr(&self) -> io::Result<VsockAddr> {
+        Ok(socket::getpeername(self.inner.as_raw_fd())?)
+    }
+}
+
+impl AsyncRead for VsockStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            match guard.try_io(|fd| Ok(socket::recv(fd.as_raw_fd(), unfilled, MsgFlags::empty())?))
+            {
+                Ok(Ok(read)) => {
+                    buf.advance(read);
+                    return Poll::Ready(Ok(()));
+                }
+                Ok(Err(e)) => return Poll::Ready(Err(e)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for VsockStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.inner.poll_write_ready(cx))?;
+            match guard.try_io(|fd| Ok(socket::send(fd.as_raw_fd(), buf, MsgFlags::MSG_NOSIGNAL)?))
+            {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(socket::shutdown(
+            self.inner.as_raw_fd(),
+            Shutdown::Write,
+        )?))
+    }
+}
+
+/// Serve `app` on every connection accepted by `listener`
+pub async fn serve(listener: VsockListener, app: Router) -> io::Result<()> {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("vsock accept failed: {}", e);
+                continue;
+            }
+        };
+        debug!(
+            "vsock connection from cid {} port {}",
+            peer.cid(),
+            peer.port()
+        );
+
+        let service = app
+            .clone()
+            .map_request(|request: axum::http::Request<Incoming>| {
+                request.map(axum::body::Body::new)
+            });
+        tokio::spawn(async move {
+            // Errors here are clients hanging up mid-request
+            let _ = Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(
+                    TokioIo::new(stream),
+                    TowerToHyperService::new(service),
+                )
+                .await;
+        });
+    }
+}
+
+/// Pause after a failed TCP accept, e.g. out of file descriptors, so the
+/// forwarder does not spin while the condition lasts
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Relay every TCP connection on `listener` to `port` of the VM at `cid`
+///
+/// Runs on the parent instance so HTTP clients can reach an enclave.
+pub async fn forward_tcp(listener: TcpListener, cid: u32, port: u32) -> io::Result<()> {
+    loop {
+        let (mut tcp, client) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("TCP accept failed: {}", e);
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let mut vsock = match VsockStream::connect(cid, port).await {
+                Ok(vsock) => vsock,
+                Err(e) => {
+                    warn!(
+                        "Dropping {}: cannot reach cid {} port {}: {}",
+                        client, cid, port, e
+                    );
+                    return;
+                }
+            };
+            match tokio::io::copy_bidirectional(&mut tcp, &mut vsock).await {
+                Ok((sent, received)) => debug!(
+                    "{} closed after {} bytes out, {} bytes back",
+                    client, sent, received
+                ),
+                Err(e) => debug!("{} closed: {}", client, e),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Listener on a free loopback port
+    ///
+    /// Tests using it are ignored by default; run them with
+    /// `cargo test -- --ignored` after `modprobe vsock_loopback`.
+    pub(crate) fn loopback_listener() -> (VsockListener, u32) {
+        let listener = VsockListener::bind(VMADDR_CID_LOCAL, VMADDR_PORT_ANY)
+            .expect("no loopback vsock (modprobe vsock_loopback)");
+        let port = listener.local_addr().unwrap().port();
+        (listener, port)
+    }
+
+    fn app() -> Router {
+        Router::new().route("/health_check", axum::routing::get(|| async { "healthy" }))
+    }
+
+    #[tokio::test]
+    async fn test_stream_io() {
+        // The stream only relies on the socket being connected, so a
+        // socketpair stands in for a vsock connection
+        let (a, b) = socket::socketpair(
+            AddressFamily::Unix,
+            SockType::Stream,
+            None,
+            SockFlag::SOCK_NONBLOCK | SockFlag::SOCK_CLOEXEC,
+        )
+        .unwrap();
+        let (mut a, mut b) = (
+            VsockStream::from_fd(a).unwrap(),
+            VsockStream::from_fd(b).unwrap(),
+        );
+
+        let payload = vec![7u8; 1 << 20];
+        let writer = tokio::spawn(async move {
+            a.write_all(&payload).await.unwrap();
+            a.shutdown().await.unwrap();
+            a
+        });
+        let mut received = Vec::new();
+        b.read_to_end(&mut received).await.unwrap();
+        assert_eq!(received.len(), 1 << 20);
+        assert!(received.iter().all(|&byte| byte == 7));
+
+        b.write_all(b"pong").await.unwrap();
+        let mut a = writer.await.unwrap();
+        let mut reply = [0u8; 4];
+        a.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"pong");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the vsock_loopback kernel module"]
+    async fn test_serve_over_loopback_vsock() {
+        let (listener, port) = loopback_listener();
+        tokio::spawn(serve(listener, app()));
+
+        let mut stream = VsockStream::connect(VMADDR_CID_LOCAL, port).await.unwrap();
+        assert_eq!(stream.peer_addr().unwrap().port(), port);
+        stream
+            .write_all(b"GET /health_check HTTP/1.1\r\nHost: enclave\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("healthy"));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the vsock_loopback kernel module"]
+    async fn test_forward_tcp_to_vsock() {
+        let (listener, port) = loopback_listener();
+        tokio::spawn(serve(listener, app()));
+
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health_check", tcp.local_addr().unwrap());
+        tokio::spawn(forward_tcp(tcp, VMADDR_CID_LOCAL, port));
+
+        let body = reqwest::get(&url).await.unwrap().text().await.unwrap();
+        assert_eq!(body, "healthy");
+
+        // Nothing listens on a port freed again: the client is disconnected
+        let (closed, closed_port) = loopback_listener();
+        drop(closed);
+        let dead = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health_check", dead.local_addr().unwrap());
+        tokio::spawn(forward_tcp(dead, VMADDR_CID_LOCAL, closed_port));
+        assert!(reqwest::get(&url).await.is_err());
+    }
+}
@@ -6,7 +6,7 @@ set -e
 cd "$(dirname "$0")"
 
 echo "Building TEE Server..."
-cargo build --release
+cargo build --release --features onnx-ort
 
 echo ""
 echo "Starting TEE Server on port 3000..."
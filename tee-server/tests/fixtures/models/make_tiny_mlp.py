@@ -0,0 +1,128 @@
+#!/usr/bin/env python3
+# Copyright (c) 2025, SynapseModel Team
+# SPDX-License-Identifier: Apache-2.0
+"""Generate tiny_mlp.onnx, the backend parity test model.
+
+probabilities = Softmax(Relu(input @ W1 + B1) @ W2 + B2), input [N, 4],
+output [N, 3], opset 13. The protobuf is written by hand so the script
+needs nothing beyond the standard library. Prints the reference outputs
+(computed here in float64) for the inputs used by the parity test.
+"""
+
+import math
+import struct
+from pathlib import Path
+
+
+def varint(value):
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def field(number, wire_type, payload):
+    return varint(number << 3 | wire_type) + payload
+
+
+def int_field(number, value):
+    return field(number, 0, varint(value))
+
+
+def bytes_field(number, payload):
+    if isinstance(payload, str):
+        payload = payload.encode()
+    return field(number, 2, varint(len(payload)) + payload)
+
+
+FLOAT = 1
+
+
+def tensor(name, dims, values):
+    return (
+        b"".join(int_field(1, d) for d in dims)
+        + int_field(2, FLOAT)
+        + bytes_field(8, name)
+        + bytes_field(9, struct.pack("<%df" % len(values), *values))
+    )
+
+
+def value_info(name, dims):
+    shape = b"".join(
+        bytes_field(1, bytes_field(2, d) if isinstance(d, str) else int_field(1, d))
+        for d in dims
+    )
+    tensor_type = int_field(1, FLOAT) + bytes_field(2, shape)
+    return bytes_field(1, name) + bytes_field(2, bytes_field(1, tensor_type))
+
+
+def node(op_type, inputs, outputs, attributes=b""):
+    return (
+        b"".join(bytes_field(1, i) for i in inputs)
+        + b"".join(bytes_field(2, o) for o in outputs)
+        + bytes_field(4, op_type)
+        + attributes
+    )
+
+
+W1 = [0.5, -0.25, 0.75, -0.5, 0.25, 1.0, 0.125, -0.75, 0.5, 1.0, -1.0, 0.25]
+B1 = [0.1, -0.2, 0.05]
+W2 = [1.0, -0.5, 0.25, -0.75, 0.5, 1.25, 0.5, 0.5, -1.0]
+B2 = [0.0, 0.1, -0.1]
+
+INPUTS = [
+    [0.0, 0.0, 0.0, 0.0],
+    [1.0, 2.0, 3.0, 4.0],
+    [-1.5, 0.5, 2.5, -0.25],
+]
+
+
+def reference(row):
+    hidden = [
+        max(0.0, sum(row[i] * W1[i * 3 + j] for i in range(4)) + B1[j]) for j in range(3)
+    ]
+    logits = [sum(hidden[i] * W2[i * 3 + j] for i in range(3)) + B2[j] for j in range(3)]
+    top = max(logits)
+    exps = [math.exp(v - top) for v in logits]
+    return [e / sum(exps) for e in exps]
+
+
+def main():
+    # Softmax axis attribute: name=1, i=3, type=2 (INT)
+    axis = bytes_field(5, bytes_field(1, "axis") + int_field(3, 1) + int_field(20, 2))
+    graph = (
+        bytes_field(1, node("MatMul", ["input", "W1"], ["h0"]))
+        + bytes_field(1, node("Add", ["h0", "B1"], ["h1"]))
+        + bytes_field(1, node("Relu", ["h1"], ["h2"]))
+        + bytes_field(1, node("MatMul", ["h2", "W2"], ["l0"]))
+        + bytes_field(1, node("Add", ["l0", "B2"], ["logits"]))
+        + bytes_field(1, node("Softmax", ["logits"], ["probabilities"], axis))
+        + bytes_field(2, "tiny_mlp")
+        + bytes_field(5, tensor("W1", [4, 3], W1))
+        + bytes_field(5, tensor("B1", [3], B1))
+        + bytes_field(5, tensor("W2", [3, 3], W2))
+        + bytes_field(5, tensor("B2", [3], B2))
+        + bytes_field(11, value_info("input", ["N", 4]))
+        + bytes_field(12, value_info("probabilities", ["N", 3]))
+    )
+    model = (
+        int_field(1, 8)
+        + bytes_field(2, "synapsemodel-fixtures")
+        + bytes_field(7, graph)
+        + bytes_field(8, bytes_field(1, "") + int_field(2, 13))
+    )
+
+    out = Path(__file__).with_name("tiny_mlp.onnx")
+    out.write_bytes(model)
+    print("wrote", out, len(model), "bytes")
+    for row in INPUTS:
+        print(row, "->", ["%.9f" % p for p in reference(row)])
+
+
+if __name__ == "__main__":
+    main()
@@ -3,51 +3,93 @@
 
 use reqwest::Client;
 use serde_json::json;
+use std::path::PathBuf;
+use synapsemodel_tee_server::{models::ModelLoader, ServerBuilder, ServerConfig};
+
+/// Models directory serving `mnist-classifier` on the mock backend
+fn models_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("integration-{}-{}", test, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("mnist.onnx"), b"mock").unwrap();
+    std::fs::write(
+        dir.join("models.toml"),
+        r#"
+            [[model]]
+            id = "mnist-classifier"
+            file = "mnist.onnx"
+            backend = "mock"
+
+            [model.input]
+            field = "pixels"
+            dtype = "f32"
+            shape = [1, 784]
+
+            [model.postprocessing]
+            kind = "classification"
+        "#,
+    )
+    .unwrap();
+    dir
+}
+
+/// Serve the app on an ephemeral port and return its base URL, and the
+/// models directory for the test to remove
+async fn spawn_server(test: &str) -> (String, PathBuf) {
+    let dir = models_dir(test);
+    let app = ServerBuilder::new(ServerConfig::default())
+        .keypair(synapsemodel_tee_server::common::generate_keypair())
+        .model_loader(ModelLoader::from_dir(&dir).unwrap())
+        .build();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (url, dir)
+}
 
 #[tokio::test]
 async fn test_health_check() {
+    let (server, dir) = spawn_server("health").await;
     let client = Client::new();
-    
-    let response = client
-        .get("http://localhost:3000/health_check")
-        .send()
-        .await;
-    
+
+    let response = client.get(format!("{}/health_check", server)).send().await;
+
     assert!(response.is_ok(), "Health check should succeed");
-    
+
     let response = response.unwrap();
     assert_eq!(response.status(), 200);
-    
+
     let body: serde_json::Value = response.json().await.unwrap();
     assert_eq!(body["status"], "healthy");
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
 #[tokio::test]
 async fn test_public_key() {
+    let (server, dir) = spawn_server("public-key").await;
     let client = Client::new();
-    
-    let response = client
-        .get("http://localhost:3000/public_key")
-        .send()
-        .await;
-    
+
+    let response = client.get(format!("{}/get_pk", server)).send().await;
+
     assert!(response.is_ok(), "Public key endpoint should succeed");
-    
+
     let response = response.unwrap();
     assert_eq!(response.status(), 200);
-    
+
     let body: serde_json::Value = response.json().await.unwrap();
     assert!(body["public_key"].is_string());
     assert_eq!(body["format"], "ed25519-hex");
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
+#[cfg(feature = "mock-inference")]
 #[tokio::test]
 async fn test_mnist_inference() {
+    let (server, dir) = spawn_server("mnist").await;
     let client = Client::new();
-    
+
     // Create MNIST input (784 zeros)
     let pixels = vec![0.0f32; 784];
-    
+
     let request_body = json!({
         "payload": {
             "job_id": "test-job-1",
@@ -57,36 +99,38 @@ async fn test_mnist_inference() {
             }
         }
     });
-    
+
     let response = client
-        .post("http://localhost:3000/process_data")
+        .post(format!("{}/process_data", server))
         .json(&request_body)
         .send()
         .await;
-    
+
     assert!(response.is_ok(), "Inference request should succeed");
-    
+
     let response = response.unwrap();
     assert_eq!(response.status(), 200);
-    
+
     let body: serde_json::Value = response.json().await.unwrap();
-    
+
     // Check response structure
     assert!(body["response"].is_object());
     assert!(body["signature"].is_string());
-    
+
     // Check inference response
     let inference_response = &body["response"]["data"];
     assert_eq!(inference_response["job_id"], "test-job-1");
     assert_eq!(inference_response["model_id"], "mnist-classifier");
     assert!(inference_response["result"].is_object());
     assert!(inference_response["input_hash"].is_string());
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
 #[tokio::test]
 async fn test_invalid_model() {
+    let (server, dir) = spawn_server("invalid-model").await;
     let client = Client::new();
-    
+
     let request_body = json!({
         "payload": {
             "job_id": "test-job-2",
@@ -94,26 +138,29 @@ async fn test_invalid_model() {
             "input_data": {}
         }
     });
-    
+
     let response = client
-        .post("http://localhost:3000/process_data")
+        .post(format!("{}/process_data", server))
         .json(&request_body)
         .send()
         .await;
-    
+
     assert!(response.is_ok());
-    
+
     let response = response.unwrap();
     assert_eq!(response.status(), 404); // Not found
+    std::fs::remove_dir_all(&dir).unwrap();
 }
 
+#[cfg(feature = "mock-inference")]
 #[tokio::test]
 async fn test_invalid_input() {
+    let (server, dir) = spawn_server("invalid-input").await;
     let client = Client::new();
-    
+
     // MNIST requires 784 pixels, provide only 100
     let pixels = vec![0.0f32; 100];
-    
+
     let request_body = json!({
         "payload": {
             "job_id": "test-job-3",
@@ -123,15 +170,16 @@ async fn test_invalid_input() {
             }
         }
     });
-    
+
     let response = client
-        .post("http://localhost:3000/process_data")
+        .post(format!("{}/process_data", server))
         .json(&request_body)
         .send()
         .await;
-    
+
     assert!(response.is_ok());
-    
+
     let response = response.unwrap();
     assert_eq!(response.status(), 400); // Bad request
+    std::fs::remove_dir_all(&dir).unwrap();
 }